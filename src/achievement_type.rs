@@ -0,0 +1,243 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io::Read;
+use std::io::Seek;
+#[cfg(feature = "write")]
+use std::io::Write;
+use std::str::FromStr;
+
+use binrw::BinRead;
+use binrw::BinResult;
+#[cfg(feature = "write")]
+use binrw::BinWrite;
+use binrw::Endian;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Deserializer;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::Serializer;
+
+use crate::SpaceOptimizedString;
+
+/// The prototype type of an achievement, which determines the layout of its
+/// progress. Types this parser does not know are kept as [Self::Other].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum AchievementType {
+    Achievement,
+    BuildEntity,
+    ChangeSurface,
+    CombatRobotCount,
+    CompleteObjective,
+    ConstructWithRobots,
+    CreatePlatform,
+    DeconstructWithRobots,
+    DeliverByRobots,
+    DepleteResource,
+    DestroyCliff,
+    DontBuildEntity,
+    DontCraftManually,
+    DontKillManually,
+    DontResearchBeforeResearching,
+    DontUseEntityInEnergyProduction,
+    EquipArmor,
+    FinishTheGame,
+    GroupAttack,
+    Kill,
+    ModuleTransfer,
+    PlaceEquipment,
+    PlayerDamaged,
+    Produce,
+    ProducePerHour,
+    Research,
+    ResearchWithSciencePack,
+    Shoot,
+    SpaceConnectionDistanceTraveled,
+    TrainPath,
+    UseEntityInEnergyProduction,
+    UseItem,
+    /// A type name not listed above. Construct with [Self::from] rather than
+    /// directly, so that known names get their own variant.
+    Other(String),
+}
+
+impl AchievementType {
+    /// The type name as stored in the file and used in prototypes, e.g.
+    /// "produce-achievement".
+    pub fn as_str(&self) -> &str {
+        use AchievementType::*;
+        match self {
+            Achievement => "achievement",
+            BuildEntity => "build-entity-achievement",
+            ChangeSurface => "change-surface-achievement",
+            CombatRobotCount => "combat-robot-count-achievement",
+            CompleteObjective => "complete-objective-achievement",
+            ConstructWithRobots => "construct-with-robots-achievement",
+            CreatePlatform => "create-platform-achievement",
+            DeconstructWithRobots => "deconstruct-with-robots-achievement",
+            DeliverByRobots => "deliver-by-robots-achievement",
+            DepleteResource => "deplete-resource-achievement",
+            DestroyCliff => "destroy-cliff-achievement",
+            DontBuildEntity => "dont-build-entity-achievement",
+            DontCraftManually => "dont-craft-manually-achievement",
+            DontKillManually => "dont-kill-manually-achievement",
+            DontResearchBeforeResearching => "dont-research-before-researching-achievement",
+            DontUseEntityInEnergyProduction => "dont-use-entity-in-energy-production-achievement",
+            EquipArmor => "equip-armor-achievement",
+            FinishTheGame => "finish-the-game-achievement",
+            GroupAttack => "group-attack-achievement",
+            Kill => "kill-achievement",
+            ModuleTransfer => "module-transfer-achievement",
+            PlaceEquipment => "place-equipment-achievement",
+            PlayerDamaged => "player-damaged-achievement",
+            Produce => "produce-achievement",
+            ProducePerHour => "produce-per-hour-achievement",
+            Research => "research-achievement",
+            ResearchWithSciencePack => "research-with-science-pack-achievement",
+            Shoot => "shoot-achievement",
+            SpaceConnectionDistanceTraveled => "space-connection-distance-traveled-achievement",
+            TrainPath => "train-path-achievement",
+            UseEntityInEnergyProduction => "use-entity-in-energy-production-achievement",
+            UseItem => "use-item-achievement",
+            Other(name) => name,
+        }
+    }
+
+    /// False for [Self::Other].
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Other(_))
+    }
+}
+
+impl From<&str> for AchievementType {
+    fn from(name: &str) -> Self {
+        use AchievementType::*;
+        match name {
+            "achievement" => Achievement,
+            "build-entity-achievement" => BuildEntity,
+            "change-surface-achievement" => ChangeSurface,
+            "combat-robot-count-achievement" => CombatRobotCount,
+            "complete-objective-achievement" => CompleteObjective,
+            "construct-with-robots-achievement" => ConstructWithRobots,
+            "create-platform-achievement" => CreatePlatform,
+            "deconstruct-with-robots-achievement" => DeconstructWithRobots,
+            "deliver-by-robots-achievement" => DeliverByRobots,
+            "deplete-resource-achievement" => DepleteResource,
+            "destroy-cliff-achievement" => DestroyCliff,
+            "dont-build-entity-achievement" => DontBuildEntity,
+            "dont-craft-manually-achievement" => DontCraftManually,
+            "dont-kill-manually-achievement" => DontKillManually,
+            "dont-research-before-researching-achievement" => DontResearchBeforeResearching,
+            "dont-use-entity-in-energy-production-achievement" => DontUseEntityInEnergyProduction,
+            "equip-armor-achievement" => EquipArmor,
+            "finish-the-game-achievement" => FinishTheGame,
+            "group-attack-achievement" => GroupAttack,
+            "kill-achievement" => Kill,
+            "module-transfer-achievement" => ModuleTransfer,
+            "place-equipment-achievement" => PlaceEquipment,
+            "player-damaged-achievement" => PlayerDamaged,
+            "produce-achievement" => Produce,
+            "produce-per-hour-achievement" => ProducePerHour,
+            "research-achievement" => Research,
+            "research-with-science-pack-achievement" => ResearchWithSciencePack,
+            "shoot-achievement" => Shoot,
+            "space-connection-distance-traveled-achievement" => SpaceConnectionDistanceTraveled,
+            "train-path-achievement" => TrainPath,
+            "use-entity-in-energy-production-achievement" => UseEntityInEnergyProduction,
+            "use-item-achievement" => UseItem,
+            other => Other(other.to_string()),
+        }
+    }
+}
+
+impl FromStr for AchievementType {
+    type Err = Infallible;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(name))
+    }
+}
+
+impl AsRef<str> for AchievementType {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for AchievementType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+/// By type name, so that [Self::Other] sorts among the known types.
+impl Ord for AchievementType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialOrd for AchievementType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl BinRead for AchievementType {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        SpaceOptimizedString::read_options(reader, endian, args).map(|name| Self::from(&*name))
+    }
+}
+
+#[cfg(feature = "write")]
+impl BinWrite for AchievementType {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        SpaceOptimizedString::from(self.as_str()).write_options(writer, endian, args)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for AchievementType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for AchievementType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Self::from(name.as_str()))
+    }
+}
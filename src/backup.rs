@@ -0,0 +1,280 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Timestamped snapshots of achievements files in a backup directory, by
+//! default `backups` in this tool's configuration directory.
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use factorio_achievements_editor::locate;
+
+const PREFIX: &str = "achievements-";
+const SUFFIX: &str = ".dat";
+
+pub struct Snapshot {
+    /// File name, such as "achievements-20250102T030405Z.dat"
+    pub name: String,
+    pub path: PathBuf,
+    pub modified: SystemTime,
+}
+
+/// `dir` if given, or else the default backup directory.
+pub fn dir(dir: Option<&Path>) -> std::io::Result<PathBuf> {
+    match dir {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => locate::config_dir()
+            .map(|dir| dir.join("backups"))
+            .ok_or_else(|| {
+                std::io::Error::other(
+                    "Found no configuration directory to keep backups in; give one with --backup-dir",
+                )
+            }),
+    }
+}
+
+/// Write `bytes` to a new snapshot in `dir`, named after the current time,
+/// and return its path.
+pub fn create(dir: &Path, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stamp = timestamp(SystemTime::now());
+    for n in 0.. {
+        let name = match n {
+            0 => format!("{PREFIX}{stamp}{SUFFIX}"),
+            n => format!("{PREFIX}{stamp}-{n}{SUFFIX}"),
+        };
+        let path = dir.join(name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                std::io::Write::write_all(&mut file, bytes)?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("some suffix is free")
+}
+
+/// The snapshots in `dir`, oldest first. Other files are ignored.
+pub fn list(dir: &Path) -> std::io::Result<Vec<Snapshot>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !is_snapshot_name(&name) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            snapshots.push(Snapshot {
+                name,
+                path: entry.path(),
+                modified: metadata.modified()?,
+            });
+        }
+    }
+    snapshots.sort_by(|a, b| (a.modified, &a.name).cmp(&(b.modified, &b.name)));
+    Ok(snapshots)
+}
+
+/// Whether `name` is a snapshot name as written by [create], such as
+/// "achievements-20250102T030405Z.dat" or "achievements-20250102T030405Z-1.dat",
+/// and not for example the game's own "achievements-modded.dat".
+fn is_snapshot_name(name: &str) -> bool {
+    let Some(rest) = name
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.strip_suffix(SUFFIX))
+    else {
+        return false;
+    };
+    let (stamp, n) = match rest.split_once('-') {
+        Some((stamp, n)) => (stamp, Some(n)),
+        None => (rest, None),
+    };
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    stamp.len() == 16
+        && stamp.is_char_boundary(8)
+        && stamp.is_char_boundary(15)
+        && digits(&stamp[..8])
+        && &stamp[8..9] == "T"
+        && digits(&stamp[9..15])
+        && &stamp[15..] == "Z"
+        && n.is_none_or(|n| digits(n) && !n.starts_with('0'))
+}
+
+/// The snapshot in `dir` named `name`, or the newest one if `name` is
+/// "latest".
+pub fn find(dir: &Path, name: &str) -> std::io::Result<PathBuf> {
+    let snapshots = list(dir)?;
+    let found = if name == "latest" {
+        snapshots.last()
+    } else {
+        snapshots.iter().find(|snapshot| snapshot.name == name)
+    };
+    found.map(|snapshot| snapshot.path.clone()).ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("No snapshot {name} in {}", dir.display()),
+        )
+    })
+}
+
+/// `days` as a [Duration], or an error if it is too long to represent.
+pub fn days(days: u64) -> Result<Duration, String> {
+    days.checked_mul(24 * 60 * 60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("{days} days is too long an age"))
+}
+
+/// Delete the snapshots in `dir` beyond the newest `keep` and those older
+/// than `max_age`, and return the deleted ones.
+pub fn prune(
+    dir: &Path,
+    keep: Option<usize>,
+    max_age: Option<Duration>,
+) -> std::io::Result<Vec<Snapshot>> {
+    let snapshots = list(dir)?;
+    let now = SystemTime::now();
+    let excess = keep.map_or(0, |keep| snapshots.len().saturating_sub(keep));
+    let mut pruned = Vec::new();
+    for (i, snapshot) in snapshots.into_iter().enumerate() {
+        let too_old = max_age.is_some_and(|max_age| {
+            now.duration_since(snapshot.modified)
+                .is_ok_and(|age| age > max_age)
+        });
+        if i < excess || too_old {
+            std::fs::remove_file(&snapshot.path)?;
+            pruned.push(snapshot);
+        }
+    }
+    Ok(pruned)
+}
+
+/// `time` in UTC as in "20250102T030405Z".
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Civil date from days since 1970-01-01, after Howard Hinnant's
+    // days_from_civil inverse
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn snapshot_names_are_recognized() {
+        assert!(is_snapshot_name("achievements-20250102T030405Z.dat"));
+        assert!(is_snapshot_name("achievements-20250102T030405Z-1.dat"));
+        assert!(is_snapshot_name("achievements-20250102T030405Z-12.dat"));
+        assert!(!is_snapshot_name("achievements.dat"));
+        assert!(!is_snapshot_name("achievements-modded.dat"));
+        assert!(!is_snapshot_name("achievements-20250102T030405Z-01.dat"));
+        assert!(!is_snapshot_name("achievements-20250102T030405Z-.dat"));
+        assert!(!is_snapshot_name("achievements-20250102T030405.dat"));
+        assert!(!is_snapshot_name("achievements-20250102X030405Z.dat"));
+        assert!(!is_snapshot_name("achievements-2025010aT030405Z.dat"));
+        assert!(!is_snapshot_name("achievements-20250102T030405Z.dat.tmp"));
+        assert!(!is_snapshot_name("achievements-2025010éT030405Z.dat"));
+    }
+
+    #[test]
+    fn timestamp_is_utc_civil_time() {
+        let at = |secs| timestamp(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(at(0), "19700101T000000Z");
+        assert_eq!(at(86_399), "19700101T235959Z");
+        assert_eq!(at(951_782_400), "20000229T000000Z");
+        assert_eq!(at(1_735_787_045), "20250102T030405Z");
+        assert_eq!(at(4_107_542_400), "21000301T000000Z");
+    }
+
+    #[test]
+    fn created_snapshots_have_snapshot_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = create(dir.path(), b"first").unwrap();
+        let second = create(dir.path(), b"second").unwrap();
+        assert_ne!(first, second);
+        let names: Vec<String> = list(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|name| is_snapshot_name(name)));
+    }
+
+    #[test]
+    fn days_out_of_range_is_an_error() {
+        assert_eq!(days(2), Ok(Duration::from_secs(2 * 86_400)));
+        assert!(days(u64::MAX).is_err());
+        assert!(days(u64::MAX / 86_400 + 1).is_err());
+    }
+
+    #[test]
+    fn prune_deletes_only_snapshots_older_than_max_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = create(dir.path(), b"old").unwrap();
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - days(3).unwrap())
+            .unwrap();
+        let new = create(dir.path(), b"new").unwrap();
+
+        let pruned = prune(dir.path(), None, Some(days(2).unwrap())).unwrap();
+        let pruned: Vec<&Path> = pruned
+            .iter()
+            .map(|snapshot| snapshot.path.as_path())
+            .collect();
+        assert_eq!(pruned, [old.as_path()]);
+        assert!(!old.exists());
+        assert!(new.exists());
+    }
+}
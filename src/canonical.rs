@@ -0,0 +1,74 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::AchievementsDat;
+
+/// Stable line-oriented text form of an [AchievementsDat], as returned by
+/// [AchievementsDat::canonical], for keeping the file under version control.
+///
+/// Header registrations and progress entries are sorted, so that files with
+/// the same contents in a different order print the same, and each
+/// achievement is on lines of its own, so that a change to one achievement
+/// shows up in a line diff as a change to its lines only. Tracked
+/// achievements are printed in file order, which the game keeps.
+pub struct Canonical<'a> {
+    pub(crate) data: &'a AchievementsDat,
+}
+
+impl Display for Canonical<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let data = self.data;
+        let [major, minor, patch, build] = data.version;
+        writeln!(f, "version {major}.{minor}.{patch}.{build}")?;
+        writeln!(f, "unused {:#04x}", data.unused_byte())?;
+
+        let mut registrations: Vec<_> = data
+            .headers
+            .iter()
+            .flat_map(|header| {
+                header
+                    .subobjects
+                    .iter()
+                    .map(move |sub| (&header.typ, sub.id.as_ref(), sub.index))
+            })
+            .collect();
+        registrations.sort();
+        for (typ, id, index) in registrations {
+            writeln!(f, "header {typ} {id} {index}")?;
+        }
+
+        let mut contents: Vec<_> = data.contents.iter().collect();
+        contents.sort_by(|a, b| (&a.typ, &a.id).cmp(&(&b.typ, &b.id)));
+        for content in contents {
+            write!(f, "progress {} {}", content.typ, content.id)?;
+            for (name, value) in content.progress.fields() {
+                write!(f, " {name}={value}")?;
+            }
+            writeln!(f)?;
+        }
+
+        for tracked in data.tracked_ids() {
+            match tracked {
+                Ok(id) => writeln!(f, "tracked {id}")?,
+                Err(index) => writeln!(f, "tracked #{index}")?,
+            }
+        }
+        Ok(())
+    }
+}
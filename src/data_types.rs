@@ -15,18 +15,45 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::fmt::Debug;
+use std::fmt::Display;
 use std::fmt::Formatter;
+use std::io::Read;
+use std::io::Seek;
+#[cfg(feature = "write")]
+use std::io::Write;
 use std::marker::PhantomData;
 use std::ops::Deref;
-use std::ops::DerefMut;
+use std::string::FromUtf8Error;
 
 use binrw::BinRead;
+use binrw::BinResult;
+#[cfg(feature = "write")]
 use binrw::BinWrite;
+use binrw::Endian;
+#[cfg(not(feature = "write"))]
+use binrw::binread;
+#[cfg(feature = "write")]
 use binrw::binrw;
-use binrw::error::CustomError;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Deserializer;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::Serializer;
 
-#[binrw]
-#[derive(Eq, Ord, PartialEq, PartialOrd)]
+use crate::error::ElementError;
+
+#[cfg_attr(feature = "write", binrw)]
+#[cfg_attr(not(feature = "write"), binread)]
+/// A string as stored in the file: prefixed by its length in one byte, or by
+/// 255 and then its length in four bytes if it is 255 bytes or longer.
+///
+/// The contents must be valid UTF-8. Reading a string that is not fails with
+/// [Error::Utf8](crate::Error::Utf8), so a file with such an ID cannot be
+/// read at all.
+#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub struct SpaceOptimizedString {
     #[br(temp)]
     #[bw(try_calc(if value.len() < 255 { value.len().try_into() } else { Ok(255) }))]
@@ -34,7 +61,7 @@ pub struct SpaceOptimizedString {
 
     #[br(temp)]
     #[brw(if(short_len == 255))]
-    #[bw(try_calc(value.len().try_into().map(Some)))]
+    #[bw(try_calc(length_prefix(value.len()).map(Some)))]
     long_len: Option<u32>,
 
     #[br(count = long_len.unwrap_or(short_len.into()), try_map = String::from_utf8)]
@@ -42,18 +69,91 @@ pub struct SpaceOptimizedString {
     value: String,
 }
 
+/// Returned (wrapped in [binrw::Error::Custom]) when writing a string or
+/// sequence that is too long for its length prefix.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LengthOverflow {
+    pub len: usize,
+    pub prefix_type: &'static str,
+}
+
+impl Display for LengthOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Length {} does not fit in a {} length prefix",
+            self.len, self.prefix_type
+        )
+    }
+}
+
+impl std::error::Error for LengthOverflow {}
+
+fn length_prefix<L: TryFrom<usize>>(len: usize) -> Result<L, LengthOverflow> {
+    L::try_from(len).map_err(|_| LengthOverflow {
+        len,
+        prefix_type: std::any::type_name::<L>(),
+    })
+}
+
 impl Debug for SpaceOptimizedString {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         f.write_str(&self.value)
     }
 }
 
+impl Display for SpaceOptimizedString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(&self.value)
+    }
+}
+
+impl SpaceOptimizedString {
+    /// The string's bytes as stored in the file, without the length prefix.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.value.as_bytes()
+    }
+}
+
+impl From<String> for SpaceOptimizedString {
+    fn from(value: String) -> Self {
+        Self { value }
+    }
+}
+
+impl From<&str> for SpaceOptimizedString {
+    fn from(value: &str) -> Self {
+        Self::from(value.to_string())
+    }
+}
+
+impl TryFrom<Vec<u8>> for SpaceOptimizedString {
+    type Error = FromUtf8Error;
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        String::from_utf8(value).map(Self::from)
+    }
+}
+
 impl AsRef<str> for SpaceOptimizedString {
     fn as_ref(&self) -> &str {
         &self.value
     }
 }
 
+#[cfg(feature = "serde")]
+impl Serialize for SpaceOptimizedString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SpaceOptimizedString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
 impl Deref for SpaceOptimizedString {
     type Target = str;
     fn deref(&self) -> &<Self as Deref>::Target {
@@ -61,68 +161,242 @@ impl Deref for SpaceOptimizedString {
     }
 }
 
-#[binrw]
-#[derive(Debug)]
-pub struct SizedVec<L, T>
-where
-    L: Copy,
-    L: Debug,
-    for<'a> L: BinRead<Args<'a> = ()>,
-    for<'a> L: BinWrite<Args<'a> = ()>,
-    usize: TryFrom<L>,
-    L: TryFrom<usize>,
-    <L as TryFrom<usize>>::Error: CustomError + 'static,
-    T: BinRead + BinWrite + 'static,
-    for<'a> <T as BinRead>::Args<'a>: Clone,
-    for<'a> <T as BinWrite>::Args<'a>: Clone,
-    for<'a> <T as BinRead>::Args<'a>: Default,
-    for<'a> <T as BinWrite>::Args<'a>: Default,
-{
+/// A list as stored in the file: prefixed by its length as an `L`, followed
+/// by its elements. Its length can only change through methods that check
+/// that it still fits in an `L`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizedVec<L, T> {
     len_type: PhantomData<L>,
-    #[br(temp)]
-    #[bw(try_calc(L::try_from(value.len())))]
-    len: L,
-    #[br(count = len)]
     value: Vec<T>,
 }
 
-impl<L, T> Deref for SizedVec<L, T>
+impl<L, T> BinRead for SizedVec<L, T>
 where
-    L: Copy,
-    L: Debug,
     for<'a> L: BinRead<Args<'a> = ()>,
-    for<'a> L: BinWrite<Args<'a> = ()>,
     usize: TryFrom<L>,
-    L: TryFrom<usize>,
-    <L as TryFrom<usize>>::Error: CustomError + 'static,
-    T: BinRead + BinWrite + 'static,
-    for<'a> <T as BinRead>::Args<'a>: Clone,
-    for<'a> <T as BinWrite>::Args<'a>: Clone,
-    for<'a> <T as BinRead>::Args<'a>: Default,
-    for<'a> <T as BinWrite>::Args<'a>: Default,
+    for<'a> T: BinRead<Args<'a> = ()> + 'static,
 {
-    type Target = Vec<T>;
-    fn deref(&self) -> &<Self as Deref>::Target {
-        &self.value
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        (): Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let pos = reader.stream_position()?;
+        let len = L::read_options(reader, endian, ())?;
+        let count = usize::try_from(len).map_err(|_| binrw::Error::AssertFail {
+            pos,
+            message: "Length does not fit in usize".to_string(),
+        })?;
+        let mut value = Vec::with_capacity(count.min(1 << 16));
+        for index in 0..count {
+            let pos = reader.stream_position()?;
+            value.push(T::read_options(reader, endian, ()).map_err(|source| {
+                binrw::Error::Custom {
+                    pos,
+                    err: Box::new(ElementError {
+                        index,
+                        pos,
+                        is_achievement: false,
+                        source,
+                    }),
+                }
+            })?);
+        }
+        Ok(Self {
+            len_type: PhantomData,
+            value,
+        })
     }
 }
 
-impl<L, T> DerefMut for SizedVec<L, T>
+#[cfg(feature = "write")]
+impl<L, T> BinWrite for SizedVec<L, T>
 where
-    L: Copy,
-    L: Debug,
-    for<'a> L: BinRead<Args<'a> = ()>,
     for<'a> L: BinWrite<Args<'a> = ()>,
-    usize: TryFrom<L>,
     L: TryFrom<usize>,
-    <L as TryFrom<usize>>::Error: CustomError + 'static,
-    T: BinRead + BinWrite + 'static,
-    for<'a> <T as BinRead>::Args<'a>: Clone,
-    for<'a> <T as BinWrite>::Args<'a>: Clone,
-    for<'a> <T as BinRead>::Args<'a>: Default,
-    for<'a> <T as BinWrite>::Args<'a>: Default,
+    for<'a> T: BinWrite<Args<'a> = ()> + 'static,
 {
-    fn deref_mut(&mut self) -> &mut <Self as Deref>::Target {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        (): Self::Args<'_>,
+    ) -> BinResult<()> {
+        let pos = writer.stream_position()?;
+        length_prefix::<L>(self.value.len())
+            .map_err(|err| binrw::Error::Custom {
+                pos,
+                err: Box::new(err),
+            })?
+            .write_options(writer, endian, ())?;
+        self.value.write_options(writer, endian, ())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<L, T: Serialize> Serialize for SizedVec<L, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, L: TryFrom<usize>, T: Deserialize<'de>> Deserialize<'de> for SizedVec<L, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(Vec::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<L: TryFrom<usize>, T> TryFrom<Vec<T>> for SizedVec<L, T> {
+    type Error = LengthOverflow;
+    fn try_from(value: Vec<T>) -> Result<Self, Self::Error> {
+        length_prefix::<L>(value.len())?;
+        Ok(Self {
+            len_type: PhantomData,
+            value,
+        })
+    }
+}
+
+/// Read-only access to the elements; see [SizedVec::as_mut_slice] for
+/// modifying them in place.
+impl<L, T> Deref for SizedVec<L, T> {
+    type Target = [T];
+    fn deref(&self) -> &<Self as Deref>::Target {
+        &self.value
+    }
+}
+
+impl<L: TryFrom<usize>, T> SizedVec<L, T> {
+    /// Append an element, unless that would make the list too long for its
+    /// length prefix.
+    pub fn push(&mut self, element: T) -> Result<(), LengthOverflow> {
+        length_prefix::<L>(self.value.len() + 1)?;
+        self.value.push(element);
+        Ok(())
+    }
+}
+
+impl<L, T> SizedVec<L, T> {
+    /// Remove and return the element at `index`, if there is one.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        (index < self.value.len()).then(|| self.value.remove(index))
+    }
+
+    /// Keep only the elements for which `keep` returns true.
+    pub fn retain(&mut self, keep: impl FnMut(&T) -> bool) {
+        self.value.retain(keep);
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
         &mut self.value
     }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.value.iter_mut()
+    }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::BinReaderExt;
+    use binrw::BinWriterExt;
+
+    use crate::AchievementContent;
+    use crate::AchievementHeader;
+    use crate::AchievementType;
+    use crate::AchievementsDat;
+    use crate::HeaderSubobject;
+
+    use super::*;
+
+    fn write_string(value: &str) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        buf.write_le(&SpaceOptimizedString::from(value)).unwrap();
+        buf.into_inner()
+    }
+
+    /// Strings shorter than 255 bytes have a one-byte length prefix, and
+    /// longer ones 255 followed by a four-byte length.
+    #[test]
+    fn string_length_prefix_boundary_round_trips() {
+        for len in [0, 1, 254, 255, 256, 65_536] {
+            let value = "a".repeat(len);
+            let bytes = write_string(&value);
+            let (prefix, rest): (Vec<u8>, &[u8]) = if len < 255 {
+                (vec![u8::try_from(len).unwrap()], &bytes[1..])
+            } else {
+                let mut prefix = vec![255];
+                prefix.extend(u32::try_from(len).unwrap().to_le_bytes());
+                (prefix, &bytes[5..])
+            };
+            assert_eq!(bytes[..prefix.len()], prefix, "prefix of length {len}");
+            assert_eq!(rest, value.as_bytes(), "contents of length {len}");
+
+            let read: SpaceOptimizedString = Cursor::new(&bytes).read_le().unwrap();
+            assert_eq!(read.as_ref(), value, "read back length {len}");
+        }
+    }
+
+    /// A string of 255 bytes or more must not be read with a one-byte
+    /// prefix of 255 as its length.
+    #[test]
+    fn string_length_prefix_255_is_not_a_length() {
+        let bytes = write_string(&"b".repeat(255));
+        assert_eq!(bytes.len(), 1 + 4 + 255);
+        let mut reader = Cursor::new(&bytes);
+        let read: SpaceOptimizedString = reader.read_le().unwrap();
+        assert_eq!(read.len(), 255);
+        assert_eq!(reader.position(), 260);
+    }
+
+    /// More entries than fit in an `i16`, with IDs too long for a one-byte
+    /// length prefix.
+    #[test]
+    fn large_file_round_trips() {
+        const COUNT: usize = 40_000;
+        let ids: Vec<String> = (0..COUNT)
+            .map(|i| format!("{i:05}-{}", "x".repeat(300)))
+            .collect();
+        let subobjects = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| HeaderSubobject {
+                id: id.as_str().into(),
+                index: u16::try_from(i + 1).unwrap(),
+            })
+            .collect::<Vec<_>>();
+        let contents = ids
+            .iter()
+            .map(|id| AchievementContent::new(AchievementType::Kill, id).unwrap())
+            .collect::<Vec<_>>();
+        let data = AchievementsDat {
+            version: [2, 0, 72, 0],
+            unused: [0],
+            headers: vec![AchievementHeader {
+                typ: AchievementType::Kill,
+                subobjects: subobjects.try_into().unwrap(),
+            }]
+            .try_into()
+            .unwrap(),
+            contents: contents.try_into().unwrap(),
+            tracked: vec![1, u16::try_from(COUNT).unwrap()],
+        };
+
+        let bytes = data.to_bytes().unwrap();
+        let parsed = AchievementsDat::parse(&mut Cursor::new(&bytes))
+            .unwrap()
+            .data;
+        assert_eq!(parsed.contents().len(), COUNT);
+        assert_eq!(parsed.headers()[0].subobjects().len(), COUNT);
+        assert_eq!(parsed.contents()[COUNT - 1].id(), ids[COUNT - 1]);
+        assert!(parsed == data);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
 }
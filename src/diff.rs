@@ -0,0 +1,204 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::AchievementContent;
+use crate::AchievementType;
+use crate::AchievementsDat;
+use crate::FieldValue;
+use crate::dump::unit;
+
+/// Differences between two [AchievementsDat]s, as returned by
+/// [AchievementsDat::diff], matching achievements by ID.
+#[derive(Debug)]
+pub struct Diff<'a> {
+    pub old_version: [u16; 4],
+    pub new_version: [u16; 4],
+    /// Sorted by ID.
+    pub changes: Vec<Change<'a>>,
+    /// Value of each progress field at which its achievement is completed,
+    /// by ID and field name.
+    targets: BTreeMap<(&'a str, &'static str), f64>,
+}
+
+#[derive(Debug)]
+pub enum Change<'a> {
+    Added(&'a AchievementContent),
+    Removed(&'a AchievementContent),
+    /// The same ID has a different type, so its fields are not compared.
+    TypeChanged {
+        id: &'a str,
+        old: &'a AchievementType,
+        new: &'a AchievementType,
+    },
+    FieldChanged {
+        id: &'a str,
+        field: &'static str,
+        old: FieldValue<'a>,
+        new: FieldValue<'a>,
+    },
+}
+
+impl<'a> Diff<'a> {
+    /// Whether the two files have the same version and progress.
+    pub fn is_empty(&self) -> bool {
+        self.old_version == self.new_version && self.changes.is_empty()
+    }
+
+    /// Show how far changed fields are towards these targets, given by ID
+    /// and field name, as in "(+3421, now 86% of target)".
+    pub fn targets(self, targets: BTreeMap<(&'a str, &'static str), f64>) -> Self {
+        Self { targets, ..self }
+    }
+}
+
+pub(crate) fn diff<'a>(old: &'a AchievementsDat, new: &'a AchievementsDat) -> Diff<'a> {
+    let by_id = |data: &'a AchievementsDat| -> BTreeMap<&'a str, &'a AchievementContent> {
+        data.contents
+            .iter()
+            .map(|content| (content.id(), content))
+            .collect()
+    };
+    let old_contents = by_id(old);
+    let new_contents = by_id(new);
+
+    let mut changes = Vec::new();
+    for (id, old_content) in &old_contents {
+        let Some(new_content) = new_contents.get(id) else {
+            changes.push(Change::Removed(old_content));
+            continue;
+        };
+        if old_content.typ() != new_content.typ() {
+            changes.push(Change::TypeChanged {
+                id,
+                old: old_content.typ(),
+                new: new_content.typ(),
+            });
+            continue;
+        }
+        for ((field, old_value), (_, new_value)) in old_content
+            .progress()
+            .fields()
+            .into_iter()
+            .zip(new_content.progress().fields())
+        {
+            if old_value.total_cmp(&new_value) != Ordering::Equal {
+                changes.push(Change::FieldChanged {
+                    id,
+                    field,
+                    old: old_value,
+                    new: new_value,
+                });
+            }
+        }
+    }
+    changes.extend(
+        new_contents
+            .iter()
+            .filter(|(id, _)| !old_contents.contains_key(*id))
+            .map(|(_, content)| Change::Added(content)),
+    );
+    changes.sort_by_key(|change| match change {
+        Change::Added(content) | Change::Removed(content) => content.id(),
+        Change::TypeChanged { id, .. } | Change::FieldChanged { id, .. } => id,
+    });
+
+    Diff {
+        old_version: old.version,
+        new_version: new.version,
+        changes,
+        targets: BTreeMap::new(),
+    }
+}
+
+fn fields(content: &AchievementContent) -> String {
+    content
+        .progress()
+        .fields()
+        .into_iter()
+        .map(|(name, value)| match unit(name) {
+            Some(unit) => format!("{name}: {value} {unit}"),
+            None => format!("{name}: {value}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The signed difference between two numbers, computed in their own width so
+/// that `f32` fields do not show widening noise.
+fn delta(old: &FieldValue, new: &FieldValue) -> Option<String> {
+    match (old, new) {
+        (FieldValue::F32(old), FieldValue::F32(new)) => Some(format!("{:+}", new - old)),
+        _ => Some(format!("{:+}", new.as_f64()? - old.as_f64()?)),
+    }
+}
+
+impl Display for Diff<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.old_version != self.new_version {
+            let [a, b, c, d] = self.old_version;
+            let [w, x, y, z] = self.new_version;
+            writeln!(f, "Version: {a}.{b}.{c}.{d} -> {w}.{x}.{y}.{z}")?;
+        }
+        for change in &self.changes {
+            match change {
+                Change::Added(content) => writeln!(
+                    f,
+                    "+ {} ({}) {}",
+                    content.id(),
+                    content.typ(),
+                    fields(content)
+                )?,
+                Change::Removed(content) => writeln!(
+                    f,
+                    "- {} ({}) {}",
+                    content.id(),
+                    content.typ(),
+                    fields(content)
+                )?,
+                Change::TypeChanged { id, old, new } => writeln!(f, "~ {id} type: {old} -> {new}")?,
+                Change::FieldChanged {
+                    id,
+                    field,
+                    old,
+                    new,
+                } => {
+                    write!(f, "~ {id} {field}: {old} -> {new}")?;
+                    if let Some(unit) = unit(field) {
+                        write!(f, " {unit}")?;
+                    }
+                    let progress = self
+                        .targets
+                        .get(&(*id, *field))
+                        .filter(|target| **target != 0.0)
+                        .zip(new.as_f64())
+                        .map(|(target, new)| format!("now {:.0}% of target", 100.0 * new / target));
+                    let notes: Vec<String> = delta(old, new).into_iter().chain(progress).collect();
+                    if notes.is_empty() {
+                        writeln!(f)?;
+                    } else {
+                        writeln!(f, " ({})", notes.join(", "))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
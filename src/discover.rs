@@ -0,0 +1,276 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Structural comparison of files written by two game versions, for
+//! contributors updating the parser after a game update. This reads the
+//! files without decoding any progress, so that it also works on files with
+//! achievement types the parser does not know yet.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// The structure of a file: its version, the types registered in its
+/// headers and the progress payload sizes of each type.
+#[derive(Debug)]
+pub struct Layout {
+    version: [u16; 4],
+    types: BTreeSet<String>,
+    /// Payload sizes seen for each type, or [None] where the payload could
+    /// not be delimited.
+    sizes: BTreeMap<String, BTreeSet<Option<usize>>>,
+    /// A payload of each type, for showing in the skeleton.
+    samples: BTreeMap<String, Vec<u8>>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| format!("Unexpected end of file at offset {:#x}", self.pos))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(
+            self.take(2)?.try_into().expect("took 2 bytes"),
+        ))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(
+            self.take(4)?.try_into().expect("took 4 bytes"),
+        ))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = match self.take(1)?[0] {
+            255 => self.u32()? as usize,
+            len => len.into(),
+        };
+        let pos = self.pos;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| format!("Invalid UTF-8 in string at offset {pos:#x}"))
+    }
+
+    /// Whether an entry with one of `types` and one of `ids` starts at `pos`.
+    fn entry_starts_at(
+        &self,
+        pos: usize,
+        types: &BTreeSet<String>,
+        ids: &BTreeSet<String>,
+    ) -> bool {
+        let mut reader = Reader {
+            bytes: self.bytes,
+            pos,
+        };
+        reader.string().is_ok_and(|typ| types.contains(&typ))
+            && reader.string().is_ok_and(|id| ids.contains(&id))
+    }
+}
+
+impl Layout {
+    /// Scan the structure of a file. `tracked_len`, the number of tracked
+    /// achievements if known, tells where the last payload ends.
+    pub fn scan(bytes: &[u8], tracked_len: Option<usize>) -> Result<Self, String> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let version = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+        reader.take(1)?;
+
+        let mut types = BTreeSet::new();
+        let mut ids = BTreeSet::new();
+        for _ in 0..reader.u16()? {
+            types.insert(reader.string()?);
+            for _ in 0..reader.u16()? {
+                ids.insert(reader.string()?);
+                reader.u16()?;
+            }
+        }
+
+        let mut entries = Vec::new();
+        for _ in 0..reader.u32()? {
+            let typ = reader.string()?;
+            reader.string()?;
+            let start = reader.pos;
+            // The payload runs until the next entry, which starts with a
+            // registered type and ID.
+            let end = (start..bytes.len())
+                .find(|pos| reader.entry_starts_at(*pos, &types, &ids))
+                .or_else(|| {
+                    tracked_len
+                        .and_then(|len| bytes.len().checked_sub(2 * len))
+                        .filter(|end| *end >= start)
+                });
+            if let Some(end) = end {
+                reader.pos = end;
+            }
+            entries.push((typ, start, end));
+        }
+
+        let mut sizes: BTreeMap<String, BTreeSet<Option<usize>>> = BTreeMap::new();
+        let mut samples = BTreeMap::new();
+        for (typ, start, end) in &entries {
+            let size = end.map(|end| end - start);
+            sizes.entry(typ.clone()).or_default().insert(size);
+            if let Some(end) = end {
+                samples
+                    .entry(typ.clone())
+                    .or_insert_with(|| bytes[*start..*end].to_vec());
+            }
+        }
+        // Without the tracked length, the last payload runs into the tracked
+        // list, so its size is only known from other entries of its type.
+        for known in sizes.values_mut() {
+            if known.len() > 1 {
+                known.remove(&None);
+            }
+        }
+
+        Ok(Self {
+            version,
+            types,
+            sizes,
+            samples,
+        })
+    }
+}
+
+/// Describe the differences from `old` to `new` and, if there are any
+/// types to add or update, a skeleton of the parser changes they need.
+pub fn compare(old: &Layout, new: &Layout) -> String {
+    let mut out = String::new();
+    let version =
+        |[major, minor, patch, build]: [u16; 4]| format!("{major}.{minor}.{patch}.{build}");
+    let size = |sizes: Option<&BTreeSet<Option<usize>>>| match sizes {
+        None => "absent".to_string(),
+        Some(sizes) => sizes
+            .iter()
+            .map(|size| match size {
+                Some(size) => format!("{size} bytes"),
+                None => "unknown size".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" or "),
+    };
+    writeln!(
+        out,
+        "Version: {} -> {}",
+        version(old.version),
+        version(new.version)
+    )
+    .unwrap();
+
+    let mut changed = false;
+    let mut skeleton = String::new();
+    for typ in old.types.union(&new.types) {
+        let (old_sizes, new_sizes) = (old.sizes.get(typ), new.sizes.get(typ));
+        match (old.types.contains(typ), new.types.contains(typ)) {
+            (false, true) => {
+                writeln!(out, "+ {typ}: {}", size(new_sizes)).unwrap();
+            }
+            (true, false) => {
+                writeln!(out, "- {typ}").unwrap();
+                changed = true;
+                continue;
+            }
+            _ if old_sizes != new_sizes && new_sizes.is_some() => {
+                writeln!(out, "~ {typ}: {} -> {}", size(old_sizes), size(new_sizes)).unwrap();
+            }
+            _ => continue,
+        }
+        changed = true;
+        let variant: String = typ
+            .trim_end_matches("-achievement")
+            .split('-')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>()
+            .concat();
+        let payload = match new_sizes.and_then(|sizes| sizes.iter().flatten().next()) {
+            Some(0) => String::new(),
+            Some(size) => format!("([u8; {size}])"),
+            None => "(/* unknown size */)".to_string(),
+        };
+        let sample = new.samples.get(typ).map(|sample| {
+            sample
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        });
+        writeln!(
+            skeleton,
+            "// {typ}{}",
+            match sample {
+                Some(sample) if !sample.is_empty() => format!(", e.g. {sample}"),
+                _ => String::new(),
+            }
+        )
+        .unwrap();
+        writeln!(skeleton, "//   enum AchievementType:      {variant},").unwrap();
+        writeln!(
+            skeleton,
+            "//   AchievementType::as_str:   {variant} => \"{typ}\","
+        )
+        .unwrap();
+        writeln!(
+            skeleton,
+            "//   AchievementType::from:     \"{typ}\" => {variant},"
+        )
+        .unwrap();
+        writeln!(
+            skeleton,
+            "//   enum AchievementProgress:  {variant}{payload},"
+        )
+        .unwrap();
+        writeln!(
+            skeleton,
+            "//   impl BinRead:              T::{variant} => {variant}{},",
+            if payload.is_empty() {
+                ""
+            } else {
+                "(read(reader, endian)?)"
+            }
+        )
+        .unwrap();
+        writeln!(
+            skeleton,
+            "//   KNOWN_TYPES:               AchievementType::{variant},"
+        )
+        .unwrap();
+    }
+    if !changed {
+        writeln!(out, "No structural differences").unwrap();
+    }
+    if !skeleton.is_empty() {
+        writeln!(out).unwrap();
+        out.push_str(&skeleton);
+    }
+    out
+}
@@ -0,0 +1,110 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::AchievementType;
+use crate::AchievementsDat;
+
+/// Human-readable summary of an [AchievementsDat], as returned by
+/// [AchievementsDat::dump]: the file version, header and tracked counts, and
+/// one line per achievement with its decoded progress.
+pub struct Dump<'a> {
+    pub(crate) data: &'a AchievementsDat,
+    pub(crate) types: &'a [AchievementType],
+}
+
+impl<'a> Dump<'a> {
+    /// Only show the achievements of these types, or all if empty. The
+    /// counts at the top still cover the whole file.
+    pub fn types(self, types: &'a [AchievementType]) -> Self {
+        Self { types, ..self }
+    }
+}
+
+/// Unit suffix to print after the value of a progress field, if any.
+pub(crate) fn unit(field: &str) -> Option<&'static str> {
+    match field {
+        "max_j_per_h" => Some("J/h"),
+        "max_per_h" => Some("/h"),
+        "max_damage" => Some("damage"),
+        _ => None,
+    }
+}
+
+impl Display for Dump<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let data = self.data;
+        let [major, minor, patch, build] = data.version;
+        writeln!(f, "Version: {major}.{minor}.{patch}.{build}")?;
+
+        let names: BTreeMap<u16, &str> = data
+            .headers
+            .iter()
+            .flat_map(|header| header.subobjects.iter())
+            .map(|sub| (sub.index, sub.id.as_ref()))
+            .collect();
+        writeln!(
+            f,
+            "Headers: {} types, {} achievements",
+            data.headers.len(),
+            names.len()
+        )?;
+        let tracked: Vec<String> = data
+            .tracked
+            .iter()
+            .map(|index| match names.get(index) {
+                Some(name) => name.to_string(),
+                None => format!("#{index}"),
+            })
+            .collect();
+        writeln!(f, "Tracked: {} [{}]", tracked.len(), tracked.join(", "))?;
+        writeln!(f, "Progress: {} achievements", data.contents.len())?;
+
+        let contents: Vec<_> = data
+            .sorted_contents(None)
+            .into_iter()
+            .filter(|content| self.types.is_empty() || self.types.contains(&content.typ))
+            .collect();
+        let typ_width = contents
+            .iter()
+            .map(|c| c.typ.as_str().len())
+            .max()
+            .unwrap_or(0);
+        let id_width = contents.iter().map(|c| c.id.len()).max().unwrap_or(0);
+        for content in contents {
+            let progress: Vec<String> = content
+                .progress
+                .fields()
+                .into_iter()
+                .map(|(name, value)| match unit(name) {
+                    Some(unit) => format!("{name}: {value} {unit}"),
+                    None => format!("{name}: {value}"),
+                })
+                .collect();
+            writeln!(
+                f,
+                "  {:typ_width$}  {:id_width$}  {}",
+                content.typ.as_str(),
+                &*content.id,
+                progress.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Editing achievements as a JSON or TOML document in the user's text
+//! editor.
+
+use std::env;
+use std::fs;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use clap::ValueEnum;
+use factorio_achievements_editor::AchievementContent;
+use factorio_achievements_editor::AchievementType;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Document formats for edit --format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum DocFormat {
+    /// JSON as printed by to-json
+    Json,
+    /// The same structure as TOML
+    Toml,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => ".json",
+            Self::Toml => ".toml",
+        }
+    }
+
+    fn write<T: Serialize>(self, value: &T) -> Result<String, String> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(value)
+                .map(|text| text + "\n")
+                .map_err(|err| err.to_string()),
+            Self::Toml => toml::to_string_pretty(value)
+                .map_err(|err| format!("{err}; use --format json to edit this as JSON instead")),
+        }
+    }
+
+    pub fn read<T: DeserializeOwned>(self, text: &str) -> Result<T, String> {
+        match self {
+            Self::Json => serde_json::from_str(text).map_err(|err| err.to_string()),
+            Self::Toml => toml::from_str(text).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// Open `value` in the user's editor as a `format` document, and return the
+/// edited value once `read` accepts the document, or [None] if the document
+/// was saved unchanged. If the edited document is invalid, the user is asked
+/// whether to reopen it to fix it.
+pub fn edit<T: Serialize>(
+    value: &T,
+    format: DocFormat,
+    read: impl Fn(&str) -> Result<T, String>,
+) -> std::io::Result<Option<T>> {
+    let original = format.write(value).map_err(std::io::Error::other)?;
+    let mut file = tempfile::Builder::new()
+        .prefix("achievements-")
+        .suffix(format.extension())
+        .tempfile()?;
+    file.write_all(original.as_bytes())?;
+    file.flush()?;
+
+    loop {
+        open_editor(file.path())?;
+        let text = fs::read_to_string(file.path())?;
+        if text == original {
+            return Ok(None);
+        }
+        match read(&text) {
+            Ok(edited) => return Ok(Some(edited)),
+            Err(err) => {
+                eprintln!("Error: {}", err.trim_end());
+                if !confirm("Edit again?")? {
+                    return Err(std::io::Error::other(
+                        "The edited document is invalid; nothing was changed",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Read an edited `format` document of the achievement `original`. Its type
+/// and ID are checked before its progress, so that changing them gets its
+/// own error rather than one about the progress fields.
+pub fn read_entry(
+    format: DocFormat,
+    text: &str,
+    original: &AchievementContent,
+) -> Result<AchievementContent, String> {
+    #[derive(Deserialize)]
+    struct Key {
+        #[serde(rename = "type")]
+        typ: AchievementType,
+        id: String,
+    }
+    let key: Key = format.read(text)?;
+    if key.id != original.id() {
+        return Err(format!("The ID cannot be changed from {}", original.id()));
+    }
+    if key.typ != *original.typ() {
+        return Err(format!(
+            "The type of {} cannot be changed from {}; remove it and add it anew instead",
+            original.id(),
+            original.typ()
+        ));
+    }
+    format.read(text)
+}
+
+/// Run the editor named by `VISUAL` or `EDITOR`, which may include
+/// arguments, on `path` and wait for it to exit.
+fn open_editor(path: &Path) -> std::io::Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .map(|editor| editor.to_string_lossy().into_owned())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "windows") {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+    let mut words = editor.split_whitespace();
+    let program = words.next().expect("editor is not blank");
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|err| std::io::Error::new(err.kind(), format!("Failed to run {editor}: {err}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{editor} exited with {status}; nothing was changed"
+        )))
+    }
+}
+
+/// Ask a yes or no question on standard error, defaulting to yes unless
+/// standard input is closed.
+fn confirm(question: &str) -> std::io::Result<bool> {
+    eprint!("{question} [Y/n] ");
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+        eprintln!();
+        return Ok(false);
+    }
+    Ok(!answer.trim().to_lowercase().starts_with('n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn golem() -> AchievementContent {
+        AchievementContent::new(AchievementType::PlayerDamaged, "golem").unwrap()
+    }
+
+    #[test]
+    fn read_entry_accepts_unchanged_key() {
+        for format in [DocFormat::Json, DocFormat::Toml] {
+            let text = format.write(&golem()).unwrap();
+            assert_eq!(read_entry(format, &text, &golem()), Ok(golem()));
+        }
+    }
+
+    #[test]
+    fn read_entry_rejects_changed_type() {
+        for format in [DocFormat::Json, DocFormat::Toml] {
+            let text = format
+                .write(&golem())
+                .unwrap()
+                .replace("player-damaged-achievement", "kill-achievement");
+            let err = read_entry(format, &text, &golem()).unwrap_err();
+            assert!(
+                err.starts_with(
+                    "The type of golem cannot be changed from player-damaged-achievement"
+                ),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_entry_rejects_changed_id() {
+        let text = DocFormat::Json
+            .write(&golem())
+            .unwrap()
+            .replace("golem", "steamrolled");
+        assert_eq!(
+            read_entry(DocFormat::Json, &text, &golem()),
+            Err("The ID cannot be changed from golem".to_string())
+        );
+    }
+}
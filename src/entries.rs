@@ -0,0 +1,163 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+
+use crate::AchievementContent;
+use crate::AchievementHeader;
+use crate::AchievementType;
+use crate::AchievementsDat;
+use crate::HeaderSubobject;
+
+pub(crate) fn insert(
+    data: &mut AchievementsDat,
+    content: AchievementContent,
+) -> Result<(), String> {
+    if data.get(content.id()).is_some() {
+        return Err(format!("Achievement {} already exists", content.id()));
+    }
+    register(data, content.typ(), content.id(), None)?;
+    data.contents.push(content).map_err(|err| err.to_string())
+}
+
+pub(crate) fn remove(data: &mut AchievementsDat, id: &str) -> Option<AchievementContent> {
+    let index = data
+        .contents
+        .iter()
+        .position(|content| content.id() == id)?;
+    let removed = data.contents.remove(index)?;
+    if data.get(id).is_none() {
+        let mut header_indices = Vec::new();
+        for header in data.headers.iter_mut() {
+            header.subobjects.retain(|sub| {
+                if sub.id.as_ref() == id {
+                    header_indices.push(sub.index);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        data.headers.retain(|header| !header.subobjects.is_empty());
+        data.tracked.retain(|index| !header_indices.contains(index));
+    }
+    Some(removed)
+}
+
+pub(crate) fn restore(
+    data: &mut AchievementsDat,
+    from: &AchievementsDat,
+    id: &str,
+) -> Result<(), String> {
+    let theirs = from
+        .get(id)
+        .ok_or_else(|| format!("No achievement with ID {id} in the backup"))?;
+    put(data, theirs.clone(), header_index(from, id))
+}
+
+pub(crate) fn put(
+    data: &mut AchievementsDat,
+    content: AchievementContent,
+    index: Option<u16>,
+) -> Result<(), String> {
+    match data.get_mut(content.id()) {
+        Some(ours) if ours.typ() == content.typ() => {
+            ours.progress = content.progress;
+            Ok(())
+        }
+        _ => {
+            let id = content.id().to_string();
+            remove(data, &id);
+            register(data, content.typ(), &id, index)?;
+            data.contents
+                .push(content)
+                .map_err(|err| format!("{id}: {err}"))
+        }
+    }
+}
+
+pub(crate) fn track(data: &mut AchievementsDat, id: &str) -> Result<bool, String> {
+    let index = header_index(data, id)
+        .ok_or_else(|| format!("No achievement with ID {id} in the headers"))?;
+    if data.tracked.contains(&index) {
+        Ok(false)
+    } else {
+        data.tracked.push(index);
+        Ok(true)
+    }
+}
+
+pub(crate) fn untrack(data: &mut AchievementsDat, id: &str) -> bool {
+    let Some(index) = header_index(data, id) else {
+        return false;
+    };
+    let len = data.tracked.len();
+    data.tracked.retain(|tracked| *tracked != index);
+    data.tracked.len() != len
+}
+
+pub(crate) fn header_index(data: &AchievementsDat, id: &str) -> Option<u16> {
+    data.headers
+        .iter()
+        .flat_map(|header| header.subobjects.iter())
+        .find(|sub| sub.id.as_ref() == id)
+        .map(|sub| sub.index)
+}
+
+/// Register `id` in the headers of `data` if it is not already, with the
+/// header index `preferred` unless that is missing or taken, in which case
+/// the next unused index is used.
+pub(crate) fn register(
+    data: &mut AchievementsDat,
+    typ: &AchievementType,
+    id: &str,
+    preferred: Option<u16>,
+) -> Result<(), String> {
+    let subobjects = || {
+        data.headers
+            .iter()
+            .flat_map(|header| header.subobjects.iter())
+    };
+    if subobjects().any(|sub| sub.id.as_ref() == id) {
+        return Ok(());
+    }
+    let used: BTreeSet<u16> = subobjects().map(|sub| sub.index).collect();
+    let index = match preferred {
+        Some(index) if !used.contains(&index) => index,
+        _ => match used.last().map(|last| last.checked_add(1)) {
+            None => 0,
+            Some(Some(next)) => next,
+            // Past the highest index, reuse a free one below it instead
+            Some(None) => (0..=u16::MAX)
+                .find(|index| !used.contains(index))
+                .ok_or_else(|| format!("{id}: no free header index left"))?,
+        },
+    };
+    let subobject = HeaderSubobject {
+        id: id.into(),
+        index,
+    };
+    match data.headers.iter_mut().find(|header| header.typ == *typ) {
+        Some(header) => header.subobjects.push(subobject),
+        None => data.headers.push(AchievementHeader {
+            typ: typ.clone(),
+            subobjects: vec![subobject]
+                .try_into()
+                .expect("a single element fits any length prefix"),
+        }),
+    }
+    .map_err(|err| format!("{id}: {err}"))
+}
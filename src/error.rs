@@ -0,0 +1,243 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io::ErrorKind;
+use std::string::FromUtf8Error;
+
+use crate::LengthOverflow;
+
+/// Errors from parsing or serializing an achievements file.
+#[derive(Debug)]
+pub enum Error {
+    /// The file does not have the expected layout.
+    Parse {
+        /// Byte offset of the field that failed to parse, or of the start of
+        /// the enclosing entry if the exact position is not known.
+        offset: u64,
+        /// Index in the file's progress entries of the achievement being
+        /// parsed, if the error is within one.
+        achievement: Option<usize>,
+        message: String,
+    },
+    /// A progress entry has a type that this parser has no decoder for, so
+    /// the rest of the file cannot be parsed.
+    UnknownAchievementType {
+        typ: String,
+        offset: u64,
+        achievement: Option<usize>,
+    },
+    /// The file was written by a game version whose layout this parser does
+    /// not know.
+    UnsupportedVersion {
+        version: [u16; 4],
+    },
+    /// A string in the file is not valid UTF-8.
+    Utf8 {
+        offset: u64,
+        achievement: Option<usize>,
+        source: FromUtf8Error,
+    },
+    /// A string or list is too long to be serialized.
+    LengthOverflow(LengthOverflow),
+    /// Serialized data did not parse back to the data it was serialized
+    /// from, so it was not returned.
+    RoundTrip,
+    Io(std::io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let location =
+            |f: &mut Formatter<'_>, offset: &u64, achievement: &Option<usize>| match achievement {
+                Some(index) => write!(f, " in achievement #{index} at offset {offset:#x}"),
+                None => write!(f, " at offset {offset:#x}"),
+            };
+        match self {
+            Self::Parse {
+                offset,
+                achievement,
+                message,
+            } => {
+                write!(f, "Failed to parse file")?;
+                location(f, offset, achievement)?;
+                write!(f, ": {message}")
+            }
+            Self::UnknownAchievementType {
+                typ,
+                offset,
+                achievement,
+            } => {
+                write!(f, "Unknown achievement type {typ}")?;
+                location(f, offset, achievement)
+            }
+            Self::UnsupportedVersion { version } => UnsupportedVersion(*version).fmt(f),
+            Self::Utf8 {
+                offset,
+                achievement,
+                source,
+            } => {
+                write!(f, "Invalid UTF-8 in string")?;
+                location(f, offset, achievement)?;
+                write!(f, ": {source}")
+            }
+            Self::LengthOverflow(err) => err.fmt(f),
+            Self::RoundTrip => write!(
+                f,
+                "Serialized file would not parse back to the same contents"
+            ),
+            Self::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Utf8 { source, .. } => Some(source),
+            Self::LengthOverflow(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Parse { .. }
+            | Self::UnknownAchievementType { .. }
+            | Self::UnsupportedVersion { .. }
+            | Self::RoundTrip => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<binrw::Error> for Error {
+    fn from(err: binrw::Error) -> Self {
+        convert(err, 0, None)
+    }
+}
+
+/// Raised by the progress decoder for a type it does not know.
+#[derive(Debug)]
+pub(crate) struct UnknownType(pub(crate) String);
+
+impl Display for UnknownType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "Unknown achievement type {}", self.0)
+    }
+}
+
+/// Raised when the file's version header predates the layout this parser
+/// knows.
+#[derive(Debug)]
+pub(crate) struct UnsupportedVersion(pub(crate) [u16; 4]);
+
+impl Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let [major, minor, patch, build] = self.0;
+        write!(
+            f,
+            "Unsupported file version {major}.{minor}.{patch}.{build}: only files written by Factorio {}.0 or later can be parsed",
+            crate::MIN_SUPPORTED_MAJOR_VERSION,
+        )
+    }
+}
+
+/// Wraps an error raised while reading the element at `index` of a
+/// length-prefixed list, which starts at `pos`.
+#[derive(Debug)]
+pub(crate) struct ElementError {
+    pub(crate) index: usize,
+    pub(crate) pos: u64,
+    /// Whether the element is an [crate::AchievementContent], as marked by
+    /// [crate::AchievementsDat] when reading its contents.
+    pub(crate) is_achievement: bool,
+    pub(crate) source: binrw::Error,
+}
+
+impl Display for ElementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "In element {}: {}", self.index, self.source)
+    }
+}
+
+/// `pos` is the innermost position known so far, for errors that carry none.
+fn convert(err: binrw::Error, pos: u64, achievement: Option<usize>) -> Error {
+    let parse = |offset, message: String| Error::Parse {
+        offset,
+        achievement,
+        message,
+    };
+    match err {
+        binrw::Error::Backtrace(backtrace) => convert(*backtrace.error, pos, achievement),
+        binrw::Error::Io(err) if err.kind() == ErrorKind::UnexpectedEof => {
+            parse(pos, "Unexpected end of file".to_string())
+        }
+        binrw::Error::Io(err) => Error::Io(err),
+        binrw::Error::AssertFail { pos, message } => parse(pos, message),
+        binrw::Error::BadMagic { pos, found } => parse(pos, format!("Bad magic: {found:?}")),
+        binrw::Error::NoVariantMatch { pos } | binrw::Error::EnumErrors { pos, .. } => {
+            parse(pos, "No variant matched".to_string())
+        }
+        binrw::Error::Custom { pos, err } => {
+            let err = match err.downcast::<ElementError>() {
+                Ok(element) => {
+                    let achievement = if element.is_achievement {
+                        Some(element.index)
+                    } else {
+                        achievement
+                    };
+                    return convert(element.source, element.pos, achievement);
+                }
+                Err(err) => err,
+            };
+            let err = match err.downcast::<UnknownType>() {
+                Ok(unknown) => {
+                    return Error::UnknownAchievementType {
+                        typ: unknown.0,
+                        offset: pos,
+                        achievement,
+                    };
+                }
+                Err(err) => err,
+            };
+            let err = match err.downcast::<UnsupportedVersion>() {
+                Ok(unsupported) => {
+                    return Error::UnsupportedVersion {
+                        version: unsupported.0,
+                    };
+                }
+                Err(err) => err,
+            };
+            let err = match err.downcast::<FromUtf8Error>() {
+                Ok(source) => {
+                    return Error::Utf8 {
+                        offset: pos,
+                        achievement,
+                        source: *source,
+                    };
+                }
+                Err(err) => err,
+            };
+            match err.downcast::<LengthOverflow>() {
+                Ok(overflow) => Error::LengthOverflow(*overflow),
+                Err(err) => parse(pos, err.to_string()),
+            }
+        }
+        err => parse(pos, err.to_string()),
+    }
+}
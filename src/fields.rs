@@ -0,0 +1,488 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io::Cursor;
+use std::str::FromStr;
+
+use binrw::BinRead;
+use binrw::Endian;
+
+use crate::AchievementContent;
+use crate::AchievementProgress;
+use crate::AchievementType;
+
+/// The value of a single decoded field, as seen through [AchievementContent::field].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldValue<'a> {
+    Str(&'a str),
+    Bytes(&'a [u8]),
+    U8(u8),
+    U32(u32),
+    F32(f32),
+    F64(f64),
+}
+
+impl FieldValue<'_> {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Str(_) | Self::Bytes(_) => None,
+            Self::U8(v) => Some((*v).into()),
+            Self::U32(v) => Some((*v).into()),
+            Self::F32(v) => Some((*v).into()),
+            Self::F64(v) => Some(*v),
+        }
+    }
+
+    /// Total order over values of the same kind. Numbers of different widths
+    /// compare by value; values of different kinds compare by kind.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Str(a), Self::Str(b)) => a.cmp(b),
+            (Self::Bytes(a), Self::Bytes(b)) => a.cmp(b),
+            (Self::F32(a), Self::F32(b)) => a.total_cmp(b),
+            _ => match (self.as_f64(), other.as_f64()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => self.kind().cmp(&other.kind()),
+            },
+        }
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            Self::Str(_) => 0,
+            Self::Bytes(_) => 1,
+            Self::U8(_) | Self::U32(_) | Self::F32(_) | Self::F64(_) => 2,
+        }
+    }
+}
+
+impl Display for FieldValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Str(v) => f.write_str(v),
+            Self::Bytes(v) => v.iter().try_for_each(|b| write!(f, "{b:02x}")),
+            Self::U8(v) => v.fmt(f),
+            Self::U32(v) => v.fmt(f),
+            Self::F32(v) => v.fmt(f),
+            Self::F64(v) => v.fmt(f),
+        }
+    }
+}
+
+impl AchievementProgress {
+    /// Named fields of this progress value. Tuple variants have a single field
+    /// named `0`.
+    pub fn fields(&self) -> Vec<(&'static str, FieldValue<'_>)> {
+        use AchievementProgress::*;
+        use FieldValue::*;
+        match self {
+            Achievement | CompleteObjective | Research => vec![],
+            DontKillManually(v) | DontResearchBeforeResearching(v) => vec![("0", Bytes(v))],
+            ChangeSurface(v) => vec![("0", Bytes(v))],
+            BuildEntity(v)
+            | CreatePlatform(v)
+            | DeliverByRobots(v)
+            | DepleteResource(v)
+            | DestroyCliff(v)
+            | DontCraftManually(v)
+            | EquipArmor(v)
+            | FinishTheGame(v)
+            | GroupAttack(v)
+            | ModuleTransfer(v)
+            | PlaceEquipment(v)
+            | ResearchWithSciencePack(v)
+            | Shoot(v)
+            | SpaceConnectionDistanceTraveled(v)
+            | UseItem(v) => vec![("0", Bytes(v))],
+            DontBuildEntity(v) | UseEntityInEnergyProduction(v) => vec![("0", Bytes(v))],
+            CombatRobotCount(v) => vec![("0", U32(*v))],
+            ConstructWithRobots {
+                constructed,
+                unknown,
+            } => vec![
+                ("constructed", U32(*constructed)),
+                ("unknown", Bytes(unknown)),
+            ],
+            DeconstructWithRobots { deconstructed } => {
+                vec![("deconstructed", U32(*deconstructed))]
+            }
+            DontUseEntityInEnergyProduction { max_j_per_h } => {
+                vec![("max_j_per_h", F64(*max_j_per_h))]
+            }
+            Kill { max_killed } => vec![("max_killed", F64(*max_killed))],
+            PlayerDamaged {
+                max_damage,
+                survived,
+            } => vec![
+                ("max_damage", F32(*max_damage)),
+                ("survived", U8(*survived)),
+            ],
+            Produce { produced } => vec![("produced", F64(*produced))],
+            ProducePerHour { max_per_h } => vec![("max_per_h", F64(*max_per_h))],
+            TrainPath { longest_path } => vec![("longest_path", F64(*longest_path))],
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<FieldValue<'_>> {
+        self.fields()
+            .into_iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Progress with all fields zeroed for the given achievement type, or
+    /// `None` if the type is unknown.
+    pub fn default_for(typ: &AchievementType) -> Option<Self> {
+        // All fields are zero in their default state, so decoding a buffer of
+        // zeroes gives the default of any type and keeps this in sync with
+        // the decoder.
+        Self::read_options(&mut Cursor::new([0; 16]), Endian::Little, (typ,)).ok()
+    }
+
+    /// Overwrite the field `name` with `value`. Numbers are converted to the
+    /// field's type if that can be done without loss; raw byte fields only
+    /// accept bytes of the same length.
+    pub fn set_field(&mut self, name: &str, value: FieldValue<'_>) -> Result<(), String> {
+        use AchievementProgress::*;
+        match (self, name) {
+            (DontKillManually(v) | DontResearchBeforeResearching(v), "0") => set_bytes(v, value),
+            (ChangeSurface(v), "0") => set_bytes(v, value),
+            (
+                BuildEntity(v)
+                | CreatePlatform(v)
+                | DeliverByRobots(v)
+                | DepleteResource(v)
+                | DestroyCliff(v)
+                | DontCraftManually(v)
+                | EquipArmor(v)
+                | FinishTheGame(v)
+                | GroupAttack(v)
+                | ModuleTransfer(v)
+                | PlaceEquipment(v)
+                | ResearchWithSciencePack(v)
+                | Shoot(v)
+                | SpaceConnectionDistanceTraveled(v)
+                | UseItem(v),
+                "0",
+            ) => set_bytes(v, value),
+            (DontBuildEntity(v) | UseEntityInEnergyProduction(v), "0") => set_bytes(v, value),
+            (CombatRobotCount(v), "0") => set_int(v, value),
+            (ConstructWithRobots { constructed, .. }, "constructed") => set_int(constructed, value),
+            (ConstructWithRobots { unknown, .. }, "unknown") => set_bytes(unknown, value),
+            (DeconstructWithRobots { deconstructed }, "deconstructed") => {
+                set_int(deconstructed, value)
+            }
+            (DontUseEntityInEnergyProduction { max_j_per_h }, "max_j_per_h") => {
+                set_f64(max_j_per_h, value)
+            }
+            (Kill { max_killed }, "max_killed") => set_f64(max_killed, value),
+            (PlayerDamaged { max_damage, .. }, "max_damage") => set_f32(max_damage, value),
+            (PlayerDamaged { survived, .. }, "survived") => set_int(survived, value),
+            (Produce { produced }, "produced") => set_f64(produced, value),
+            (ProducePerHour { max_per_h }, "max_per_h") => set_f64(max_per_h, value),
+            (TrainPath { longest_path }, "longest_path") => set_f64(longest_path, value),
+            (progress, name) => Err(format!(
+                "No field named {name}, expected one of: {}",
+                progress
+                    .fields()
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Like [Self::set_field], but parse `value` from text according to the
+    /// current type of the field: raw bytes as hexadecimal, as printed by
+    /// [FieldValue]'s `Display` impl, and anything else as a number.
+    pub fn set_field_str(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match self.field(name) {
+            Some(FieldValue::Bytes(_)) => {
+                self.set_field(name, FieldValue::Bytes(&parse_hex(value)?))
+            }
+            // Parse single precision directly, since rounding through f64
+            // could give a different result.
+            Some(FieldValue::F32(_)) => self.set_field(name, FieldValue::Str(value)),
+            _ => self.set_field(
+                name,
+                FieldValue::F64(
+                    parse_f64(value).ok_or_else(|| format!("Expected a number, got: {value}"))?,
+                ),
+            ),
+        }
+    }
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return Err(format!("Expected an even number of hex digits, got: {s}"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| format!("Invalid hex: {s}")))
+        .collect()
+}
+
+fn set_bytes<const N: usize>(target: &mut [u8; N], value: FieldValue<'_>) -> Result<(), String> {
+    match value {
+        FieldValue::Bytes(bytes) => {
+            *target = bytes
+                .try_into()
+                .map_err(|_| format!("Expected {N} bytes, got {}", bytes.len()))?;
+            Ok(())
+        }
+        other => Err(format!("Expected {N} raw bytes, got: {other}")),
+    }
+}
+
+fn set_int<T: TryFrom<u64>>(target: &mut T, value: FieldValue<'_>) -> Result<(), String> {
+    let v = value
+        .as_f64()
+        .ok_or_else(|| format!("Expected a number, got: {value}"))?;
+    if v.fract() != 0.0 || v < 0.0 {
+        return Err(format!("Expected a non-negative integer, got: {value}"));
+    }
+    *target = T::try_from(v as u64).map_err(|_| format!("Out of range: {value}"))?;
+    Ok(())
+}
+
+fn set_f64(target: &mut f64, value: FieldValue<'_>) -> Result<(), String> {
+    *target = match value {
+        FieldValue::Str(s) => parse_f64(s),
+        other => other.as_f64(),
+    }
+    .ok_or_else(|| format!("Expected a number, got: {value}"))?;
+    Ok(())
+}
+
+fn set_f32(target: &mut f32, value: FieldValue<'_>) -> Result<(), String> {
+    let (v, finite) = match value {
+        FieldValue::F32(v) => (v, v.is_finite()),
+        FieldValue::Str(s) => (
+            parse_f32(s).ok_or_else(|| format!("Expected a number, got: {value}"))?,
+            parse_f64(s).is_some_and(f64::is_finite),
+        ),
+        other => {
+            let v = other
+                .as_f64()
+                .ok_or_else(|| format!("Expected a number, got: {value}"))?;
+            (v as f32, v.is_finite())
+        }
+    };
+    if finite && !v.is_finite() {
+        return Err(format!("Out of range for a 32-bit number: {value}"));
+    }
+    *target = v;
+    Ok(())
+}
+
+/// Text form of a non-finite float, for formats such as JSON that have no
+/// number for it: `inf`, `-inf`, `NaN` for the standard NaN, or `NaN:0x...`
+/// with the raw bits of any other NaN so that it can be restored exactly.
+#[cfg(feature = "serde")]
+pub(crate) fn non_finite_f64(v: f64) -> Option<String> {
+    non_finite_text(v, v.to_bits() == f64::NAN.to_bits(), v.to_bits())
+}
+
+/// Like [non_finite_f64], with the raw bits of an `f32` NaN.
+#[cfg(feature = "serde")]
+pub(crate) fn non_finite_f32(v: f32) -> Option<String> {
+    non_finite_text(
+        v.into(),
+        v.to_bits() == f32::NAN.to_bits(),
+        v.to_bits().into(),
+    )
+}
+
+#[cfg(feature = "serde")]
+fn non_finite_text(v: f64, standard_nan: bool, bits: u64) -> Option<String> {
+    if v.is_finite() {
+        None
+    } else if standard_nan {
+        Some("NaN".to_string())
+    } else if v.is_nan() {
+        Some(format!("NaN:{bits:#x}"))
+    } else {
+        Some(v.to_string())
+    }
+}
+
+/// Parse a number, or any text form given by [non_finite_f64].
+pub(crate) fn parse_f64(s: &str) -> Option<f64> {
+    match s.strip_prefix("NaN:0x") {
+        Some(bits) => {
+            Some(f64::from_bits(u64::from_str_radix(bits, 16).ok()?)).filter(|v| v.is_nan())
+        }
+        None => s.parse().ok(),
+    }
+}
+
+/// Parse a number, or any text form given by [non_finite_f32].
+pub(crate) fn parse_f32(s: &str) -> Option<f32> {
+    match s.strip_prefix("NaN:0x") {
+        Some(bits) => {
+            Some(f32::from_bits(u32::from_str_radix(bits, 16).ok()?)).filter(|v| v.is_nan())
+        }
+        None => s.parse().ok(),
+    }
+}
+
+impl AchievementContent {
+    /// Look up a field by path: `id`, `type`, or `progress.<field>` for a field
+    /// of [AchievementProgress::fields].
+    pub fn field(&self, path: &str) -> Option<FieldValue<'_>> {
+        match path {
+            "id" => Some(FieldValue::Str(&self.id)),
+            "type" => Some(FieldValue::Str(self.typ.as_str())),
+            _ => self.progress.field(path.strip_prefix("progress.")?),
+        }
+    }
+}
+
+/// A sort order over [AchievementContent::field] paths, parsed from strings
+/// like `id` or `progress.max_per_h desc`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SortKey {
+    pub path: String,
+    pub descending: bool,
+}
+
+impl SortKey {
+    /// Entries without the field sort last regardless of direction.
+    pub fn compare(&self, a: &AchievementContent, b: &AchievementContent) -> Ordering {
+        match (a.field(&self.path), b.field(&self.path)) {
+            (Some(a), Some(b)) if self.descending => b.total_cmp(&a),
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let path = words.next().ok_or("Empty sort expression")?.to_string();
+        let descending = match words.next() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => return Err(format!("Expected asc or desc, got: {other}")),
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("Unexpected trailing input: {extra}"));
+        }
+        check_path(&path)?;
+        Ok(Self { path, descending })
+    }
+}
+
+/// An assignment to a progress field, parsed from strings like
+/// `produced=1000`, `produced+=1000` or `max_killed*=2`. Relative assignments
+/// are evaluated against the current value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Assignment {
+    /// Field name as in [AchievementProgress::fields], without the
+    /// `progress.` prefix.
+    pub field: String,
+    pub op: AssignOp,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AssignOp {
+    Set,
+    Add,
+    Sub,
+    Mul,
+}
+
+impl Assignment {
+    pub fn apply(&self, progress: &mut AchievementProgress) -> Result<(), String> {
+        let field = self.field.as_str();
+        let value = self.value.as_str();
+        let combine: fn(f64, f64) -> f64 = match self.op {
+            AssignOp::Set => return progress.set_field_str(field, value),
+            AssignOp::Add => |a, b| a + b,
+            AssignOp::Sub => |a, b| a - b,
+            AssignOp::Mul => |a, b| a * b,
+        };
+        let current = match progress.field(field) {
+            Some(current) => current
+                .as_f64()
+                .ok_or_else(|| format!("Field {field} is not a number: {current}"))?,
+            // Let set_field report the valid field names.
+            None => return progress.set_field_str(field, value),
+        };
+        let operand = parse_f64(value).ok_or_else(|| format!("Expected a number, got: {value}"))?;
+        let result = combine(current, operand);
+        if !result.is_finite() {
+            return Err(format!(
+                "Field {field} would become {result}, which is not a finite number"
+            ));
+        }
+        progress.set_field(field, FieldValue::F64(result))
+    }
+}
+
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lhs, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected FIELD=VALUE, got: {s}"))?;
+        let (field, op) = match lhs.as_bytes().last() {
+            Some(b'+') => (&lhs[..lhs.len() - 1], AssignOp::Add),
+            Some(b'-') => (&lhs[..lhs.len() - 1], AssignOp::Sub),
+            Some(b'*') => (&lhs[..lhs.len() - 1], AssignOp::Mul),
+            _ => (lhs, AssignOp::Set),
+        };
+        let field = field.trim();
+        let field = field.strip_prefix("progress.").unwrap_or(field);
+        if field.is_empty() {
+            return Err(format!("Expected a field name before the =, got: {s}"));
+        }
+        let value = value.trim();
+        // Factorio never writes non-finite numbers, so they are refused here
+        // rather than only reported as warnings once written. Hex bytes such
+        // as "1e400000" also parse as infinity, but are meant as bytes.
+        if parse_f64(value).is_some_and(|v| !v.is_finite()) && parse_hex(value).is_err() {
+            return Err(format!("Expected a finite number, got: {value}"));
+        }
+        Ok(Self {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        })
+    }
+}
+
+pub(crate) fn check_path(path: &str) -> Result<(), String> {
+    if path == "id" || path == "type" || path.starts_with("progress.") {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected id, type or progress.<field>, got: {path}"
+        ))
+    }
+}
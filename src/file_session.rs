@@ -0,0 +1,153 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::fs::TryLockError;
+use std::io::Cursor;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tempfile::NamedTempFile;
+
+use crate::AchievementsDat;
+use crate::EditSession;
+use crate::Error;
+
+/// An [EditSession] on a file, holding an exclusive advisory lock on it from
+/// [Self::open] until dropped so that other instances cannot edit it at the
+/// same time.
+///
+/// Saving writes a new file next to it and renames it into place, so that a
+/// crash midway leaves either the old or the new contents, and refuses to if
+/// the file was changed since it was read, for example by a program that
+/// does not respect the lock.
+#[derive(Debug)]
+pub struct FileSession {
+    /// Resolved through any symlinks, so that saving replaces the target.
+    path: PathBuf,
+    file: File,
+    on_disk: Vec<u8>,
+    edits: EditSession,
+}
+
+impl FileSession {
+    /// Open, lock and parse the file at `path`. Fails immediately if another
+    /// process holds the lock.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().canonicalize()?;
+        let mut file = File::options().read(true).write(true).open(&path)?;
+        lock(&file, &path)?;
+        let mut on_disk = Vec::new();
+        file.read_to_end(&mut on_disk)?;
+        let data = AchievementsDat::parse(&mut Cursor::new(&on_disk))?.data;
+        Ok(Self {
+            path,
+            file,
+            on_disk,
+            edits: EditSession::new(data),
+        })
+    }
+
+    /// The path of the file, resolved through any symlinks.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The contents of the file as it was read or last saved.
+    pub fn on_disk(&self) -> &[u8] {
+        &self.on_disk
+    }
+
+    pub fn edits(&self) -> &EditSession {
+        &self.edits
+    }
+
+    pub fn edits_mut(&mut self) -> &mut EditSession {
+        &mut self.edits
+    }
+
+    /// Whether the file no longer has the contents it had when it was read or
+    /// last saved, for example because the game saved it in the meantime.
+    pub fn changed_on_disk(&mut self) -> std::io::Result<bool> {
+        Ok(self.read_current()? != self.on_disk)
+    }
+
+    /// Re-read the file and replay the staged edits onto its current
+    /// contents, so that they can be saved without losing changes made by
+    /// another program.
+    pub fn rebase(&mut self) -> Result<(), Error> {
+        let current = self.read_current()?;
+        let data = AchievementsDat::parse(&mut Cursor::new(&current))?.data;
+        self.edits
+            .rebase(data)
+            .map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))?;
+        self.on_disk = current;
+        Ok(())
+    }
+
+    /// Commit the staged edits and write them to the file. Fails if the file
+    /// was changed since it was read; see [Self::rebase]. Returns the number
+    /// of bytes written.
+    pub fn save(&mut self) -> std::io::Result<usize> {
+        if self.changed_on_disk()? {
+            return Err(std::io::Error::other(
+                "The file was changed by another program since it was read",
+            ));
+        }
+
+        let bytes = self
+            .edits
+            .commit()
+            .map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))?;
+        let dir = self
+            .path
+            .parent()
+            .expect("canonical file paths have a parent");
+        let mut new = NamedTempFile::new_in(dir)?;
+        // Lock the new file before it replaces the old one, so that there is
+        // no moment when the path is unlocked.
+        lock(new.as_file(), new.path())?;
+        new.as_file()
+            .set_permissions(self.file.metadata()?.permissions())?;
+        new.write_all(&bytes)?;
+        new.as_file().sync_all()?;
+        self.file = new.persist(&self.path).map_err(|err| err.error)?;
+        #[cfg(unix)]
+        File::open(dir)?.sync_all()?;
+        let written = bytes.len();
+        self.on_disk = bytes;
+        Ok(written)
+    }
+
+    /// Read through the path rather than the open file, which is no longer
+    /// the file at the path if another program replaced it.
+    fn read_current(&self) -> std::io::Result<Vec<u8>> {
+        std::fs::read(&self.path)
+    }
+}
+
+fn lock(file: &File, path: &Path) -> std::io::Result<()> {
+    file.try_lock().map_err(|err| match err {
+        TryLockError::WouldBlock => std::io::Error::new(
+            ErrorKind::WouldBlock,
+            format!("{} is locked by another process", path.display()),
+        ),
+        TryLockError::Error(err) => err,
+    })
+}
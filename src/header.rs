@@ -0,0 +1,138 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A compact summary of the file's version and section layout, for
+//! triaging files the tool has trouble with.
+
+use std::ops::Range;
+
+use factorio_achievements_editor::AchievementsDat;
+use factorio_achievements_editor::LATEST_VERIFIED_VERSION;
+use factorio_achievements_editor::VersionSupport;
+
+/// Size of the version and the unused byte that precede the headers.
+const PREAMBLE_LEN: usize = 4 * 2 + 1;
+
+/// Byte ranges of the sections of a file.
+pub struct Sections {
+    pub preamble: Range<usize>,
+    pub headers: Range<usize>,
+    pub progress: Range<usize>,
+    pub tracked: Range<usize>,
+}
+
+impl Sections {
+    /// The sections of `data`, read from a file of `len` bytes.
+    pub fn of(data: &AchievementsDat, len: usize) -> Self {
+        let headers_len = 2 + data
+            .headers()
+            .iter()
+            .map(|header| {
+                string_len(header.typ().as_str())
+                    + 2
+                    + header
+                        .subobjects()
+                        .iter()
+                        .map(|sub| string_len(sub.id()) + 2)
+                        .sum::<usize>()
+            })
+            .sum::<usize>();
+        let contents_start = PREAMBLE_LEN + headers_len;
+        let tracked_start = len - 2 * data.tracked().len();
+        Self {
+            preamble: 0..PREAMBLE_LEN,
+            headers: PREAMBLE_LEN..contents_start,
+            progress: contents_start..tracked_start,
+            tracked: tracked_start..len,
+        }
+    }
+}
+
+/// Name of the support level of files from `data`'s version.
+pub fn support_name(data: &AchievementsDat) -> &'static str {
+    match VersionSupport::of(data.version()) {
+        VersionSupport::Unsupported => "unsupported",
+        VersionSupport::Verified => "verified",
+        VersionSupport::Newer => "newer than verified",
+    }
+}
+
+/// Print the summary of `data`, read from a file of `len` bytes, to
+/// standard error, with a warning for anything unexpected.
+pub fn print(data: &AchievementsDat, len: usize) {
+    let [major, minor, patch, build] = data.version();
+    let support = VersionSupport::of(data.version());
+    let support_name = support_name(data);
+    let registered: usize = data
+        .headers()
+        .iter()
+        .map(|header| header.subobjects().len())
+        .sum();
+    let Sections {
+        headers,
+        progress,
+        tracked,
+        ..
+    } = Sections::of(data, len);
+
+    eprintln!("Version:   {major}.{minor}.{patch}.{build} ({support_name})");
+    eprintln!("Unused:    {:#04x}", data.unused_byte());
+    eprintln!(
+        "Headers:   {:#06x}..{:#06x}  {} types, {registered} achievements",
+        headers.start,
+        headers.end,
+        data.headers().len()
+    );
+    eprintln!(
+        "Progress:  {:#06x}..{:#06x}  {} achievements",
+        progress.start,
+        progress.end,
+        data.contents().len()
+    );
+    eprintln!(
+        "Tracked:   {:#06x}..{:#06x}  {} achievements",
+        tracked.start,
+        tracked.end,
+        data.tracked().len()
+    );
+    eprintln!("Size:      {len} bytes");
+
+    if let VersionSupport::Newer = support {
+        let [major, minor] = LATEST_VERIFIED_VERSION;
+        eprintln!("Warning: The file is newer than the latest verified version {major}.{minor}");
+    }
+    if data.unused_byte() != 0 {
+        eprintln!(
+            "Warning: The unused byte is {:#04x}, not 0 as in every file seen so far",
+            data.unused_byte()
+        );
+    }
+    if registered != data.contents().len() {
+        eprintln!(
+            "Warning: The headers register {registered} achievements, but there are {} progress entries",
+            data.contents().len()
+        );
+    }
+}
+
+/// Encoded size of a string, including its length prefix.
+fn string_len(s: &str) -> usize {
+    if s.len() < 255 {
+        1 + s.len()
+    } else {
+        5 + s.len()
+    }
+}
@@ -0,0 +1,316 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Journal of the edits made with `--in-place`, kept next to the edited file
+//! as `FILE.journal`, one JSON object per line. Each entry holds the prior
+//! state of only the achievements the edit changed, so that `undo` can
+//! revert the most recent edit without a backup of the whole file.
+//!
+//! Entries added back by `undo` end up last in the file rather than where
+//! they were, so the file is compared with what an edit left by its
+//! [canonical](AchievementsDat::canonical) form rather than byte for byte.
+//! That way the edits before it can still be undone in turn.
+
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use factorio_achievements_editor::AchievementContent;
+use factorio_achievements_editor::AchievementsDat;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::summary::Summary;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub command: String,
+    /// Hash of the canonical form of the file as the edit left it, to tell if
+    /// it has been changed since.
+    after: String,
+    achievements: Vec<Prior>,
+    /// IDs of the tracked achievements before the edit.
+    tracked: Vec<String>,
+    /// What the edit did, as printed when it was made. Missing in entries
+    /// recorded before summaries were kept.
+    #[serde(default)]
+    pub summary: Option<Summary>,
+}
+
+/// An achievement as it was before an edit.
+#[derive(Debug, Deserialize, Serialize)]
+struct Prior {
+    id: String,
+    /// `None` if the edit added the achievement.
+    before: Option<AchievementContent>,
+    /// Header index before the edit.
+    index: Option<u16>,
+}
+
+impl Entry {
+    /// Revert the edit on `data`.
+    pub fn revert(&self, mut data: AchievementsDat) -> Result<AchievementsDat, String> {
+        for prior in &self.achievements {
+            match &prior.before {
+                Some(content) => data.put_entry(content.clone(), prior.index)?,
+                None => {
+                    data.remove_entry(&prior.id);
+                }
+            }
+        }
+        // Track anew in the original order
+        for id in tracked(&data) {
+            data.untrack(&id);
+        }
+        for id in &self.tracked {
+            data.track(id)?;
+        }
+        Ok(data)
+    }
+
+    /// Whether `data` is as the edit left it.
+    pub fn left(&self, data: &AchievementsDat) -> bool {
+        self.after == hash(data)
+    }
+}
+
+pub fn path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".journal");
+    PathBuf::from(name)
+}
+
+/// Append an entry for the edit of `file` by `command` from `before` to
+/// `after`, summarized by `summary`, unless it changed nothing.
+pub fn record(
+    file: &Path,
+    command: &str,
+    before: &AchievementsDat,
+    after: &AchievementsDat,
+    summary: &Summary,
+) -> std::io::Result<()> {
+    let mut ids: Vec<&str> = before
+        .contents()
+        .iter()
+        .chain(after.contents().iter())
+        .map(AchievementContent::id)
+        .collect();
+    ids.sort();
+    ids.dedup();
+    let achievements: Vec<Prior> = ids
+        .into_iter()
+        .filter(|id| {
+            before.get(id) != after.get(id) || header_index(before, id) != header_index(after, id)
+        })
+        .map(|id| Prior {
+            id: id.to_string(),
+            before: before.get(id).cloned(),
+            index: header_index(before, id),
+        })
+        .collect();
+    let tracked_before = tracked(before);
+    if achievements.is_empty() && tracked_before == tracked(after) {
+        return Ok(());
+    }
+    let entry = Entry {
+        command: command.to_string(),
+        after: hash(after),
+        achievements,
+        tracked: tracked_before,
+        summary: Some(summary.clone()),
+    };
+    let mut journal = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path(file))?;
+    writeln!(journal, "{}", serde_json::to_string(&entry)?)
+}
+
+/// The most recent entry for `file`, if any.
+pub fn last(file: &Path) -> std::io::Result<Option<Entry>> {
+    let journal = match std::fs::read_to_string(path(file)) {
+        Ok(journal) => journal,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    journal
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(std::io::Error::from))
+        .transpose()
+}
+
+/// Remove the most recent entry for `file`, and the journal with it if it
+/// was the only one.
+pub fn pop(file: &Path) -> std::io::Result<()> {
+    let path = path(file);
+    let journal = std::fs::read_to_string(&path)?;
+    let mut lines: Vec<&str> = journal
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    lines.pop();
+    if lines.is_empty() {
+        std::fs::remove_file(path)
+    } else {
+        std::fs::write(path, lines.join("\n") + "\n")
+    }
+}
+
+fn header_index(data: &AchievementsDat, id: &str) -> Option<u16> {
+    data.headers()
+        .iter()
+        .flat_map(|header| header.subobjects())
+        .find(|sub| sub.id() == id)
+        .map(|sub| sub.index())
+}
+
+fn tracked(data: &AchievementsDat) -> Vec<String> {
+    data.tracked_ids()
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(str::to_string)
+        .collect()
+}
+
+/// 64-bit FNV-1a of the canonical form of `data`, in hex. Only used to
+/// detect changes, so it need not resist tampering.
+fn hash(data: &AchievementsDat) -> String {
+    let hash = data
+        .canonical()
+        .to_string()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use factorio_achievements_editor::AchievementType;
+    use factorio_achievements_editor::FieldValue;
+
+    use super::*;
+
+    fn entry(typ: AchievementType, id: &str, field: &str, value: FieldValue) -> AchievementContent {
+        let mut content = AchievementContent::new(typ, id).unwrap();
+        content.progress_mut().set_field(field, value).unwrap();
+        content
+    }
+
+    fn before() -> AchievementsDat {
+        let mut data: AchievementsDat = serde_json::from_str(
+            r#"{"version":[2,0,72,0],"unused":[0],"headers":[],"contents":[],"tracked":[]}"#,
+        )
+        .unwrap();
+        for content in [
+            entry(
+                AchievementType::Kill,
+                "steamrolled",
+                "max_killed",
+                FieldValue::U32(7),
+            ),
+            entry(
+                AchievementType::PlayerDamaged,
+                "golem",
+                "survived",
+                FieldValue::U8(0),
+            ),
+        ] {
+            data.insert_entry(content).unwrap();
+        }
+        data.track("steamrolled").unwrap();
+        data
+    }
+
+    fn edited(mut data: AchievementsDat) -> AchievementsDat {
+        data.remove_entry("steamrolled");
+        data.put_entry(
+            entry(
+                AchievementType::PlayerDamaged,
+                "golem",
+                "survived",
+                FieldValue::U8(1),
+            ),
+            None,
+        )
+        .unwrap();
+        data.insert_entry(entry(
+            AchievementType::Produce,
+            "mass-production-1",
+            "produced",
+            FieldValue::F64(3200.0),
+        ))
+        .unwrap();
+        data.track("golem").unwrap();
+        data
+    }
+
+    fn summary(before: &AchievementsDat, after: &AchievementsDat) -> Summary {
+        Summary::new(before, after, 0, None)
+    }
+
+    #[test]
+    fn edits_that_change_nothing_are_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("achievements.dat");
+        let data = before();
+        record(&file, "set", &data, &data, &summary(&data, &data)).unwrap();
+        assert!(!path(&file).exists());
+        assert!(last(&file).unwrap().is_none());
+    }
+
+    #[test]
+    fn reverting_an_entry_restores_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("achievements.dat");
+        let before = before();
+        let after = edited(before.clone());
+        record(&file, "edit", &before, &after, &summary(&before, &after)).unwrap();
+
+        let entry = last(&file).unwrap().unwrap();
+        assert_eq!(entry.command, "edit");
+        assert!(entry.left(&after));
+        assert!(!entry.left(&before));
+        let reverted = entry.revert(after).unwrap();
+        assert_eq!(
+            reverted.canonical().to_string(),
+            before.canonical().to_string()
+        );
+        assert_eq!(tracked(&reverted), ["steamrolled"]);
+        assert!(entry.left(&edited(reverted)));
+    }
+
+    #[test]
+    fn popping_removes_the_latest_entry_and_then_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("achievements.dat");
+        let first = before();
+        let second = edited(first.clone());
+        let mut third = second.clone();
+        third.untrack("golem");
+        record(&file, "edit", &first, &second, &summary(&first, &second)).unwrap();
+        record(&file, "untrack", &second, &third, &summary(&second, &third)).unwrap();
+
+        assert_eq!(last(&file).unwrap().unwrap().command, "untrack");
+        pop(&file).unwrap();
+        assert_eq!(last(&file).unwrap().unwrap().command, "edit");
+        pop(&file).unwrap();
+        assert!(!path(&file).exists());
+        assert!(last(&file).unwrap().is_none());
+    }
+}
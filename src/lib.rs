@@ -14,138 +14,770 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod achievement_type;
+mod canonical;
 mod data_types;
+mod diff;
+mod dump;
+mod entries;
+mod error;
+mod fields;
+#[cfg(feature = "write")]
+mod file_session;
+pub mod locate;
+mod merge;
+mod pattern;
+mod query;
+mod repair;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod session;
+mod suggest;
+mod template;
+mod warnings;
 
+use std::cmp::Ordering;
 use std::collections::BTreeSet;
 use std::fmt::Debug;
+#[cfg(feature = "write")]
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Seek;
 
+pub use achievement_type::AchievementType;
+use binrw::BinRead;
+use binrw::BinResult;
+#[cfg(feature = "write")]
+use binrw::BinWrite;
+use binrw::Endian;
+#[cfg(not(feature = "write"))]
+use binrw::binread;
+#[cfg(feature = "write")]
 use binrw::binrw;
+#[cfg(feature = "write")]
+use binrw::binwrite;
 use binrw::helpers::until_eof;
-use data_types::SizedVec;
-use data_types::SpaceOptimizedString;
+pub use canonical::Canonical;
+pub use data_types::LengthOverflow;
+pub use data_types::SizedVec;
+pub use data_types::SpaceOptimizedString;
+pub use diff::Change;
+pub use diff::Diff;
+pub use dump::Dump;
+use error::ElementError;
+pub use error::Error;
+use error::UnknownType;
+use error::UnsupportedVersion;
+pub use fields::AssignOp;
+pub use fields::Assignment;
+pub use fields::FieldValue;
+pub use fields::SortKey;
+#[cfg(feature = "write")]
+pub use file_session::FileSession;
+pub use merge::MergeReport;
+pub use pattern::Pattern;
+pub use query::Query;
+pub use query::QueryValue;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+pub use session::Edit;
+pub use session::EditSession;
+pub use template::Template;
+pub use warnings::ParseOutcome;
+pub use warnings::Warning;
 
-#[binrw]
-#[derive(Debug)]
+/// Files written by game versions before this one have progress layouts
+/// that this parser does not know, so they are rejected up front with
+/// [Error::UnsupportedVersion] rather than misparsed.
+pub const MIN_SUPPORTED_MAJOR_VERSION: u16 = 2;
+
+/// Newest game version, as major and minor, whose files this parser has been
+/// checked against.
+pub const LATEST_VERIFIED_VERSION: [u16; 2] = [2, 0];
+
+/// How well files written by a given game version are supported.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VersionSupport {
+    /// Older than [MIN_SUPPORTED_MAJOR_VERSION]; parsing fails with
+    /// [Error::UnsupportedVersion].
+    Unsupported,
+    /// Between [MIN_SUPPORTED_MAJOR_VERSION] and [LATEST_VERIFIED_VERSION].
+    Verified,
+    /// Newer than [LATEST_VERIFIED_VERSION]. Such files are parsed as if
+    /// they were, but may contain achievement types or layouts this parser
+    /// does not know.
+    Newer,
+}
+
+impl VersionSupport {
+    /// Support for files whose version header is `version`, as major, minor,
+    /// patch and build.
+    pub fn of(version: [u16; 4]) -> Self {
+        let [major, minor, ..] = version;
+        if major < MIN_SUPPORTED_MAJOR_VERSION {
+            Self::Unsupported
+        } else if [major, minor] > LATEST_VERIFIED_VERSION {
+            Self::Newer
+        } else {
+            Self::Verified
+        }
+    }
+}
+
+#[cfg_attr(feature = "write", binrw)]
+#[cfg_attr(not(feature = "write"), binread)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AchievementsDat {
+    #[br(assert(version[0] >= MIN_SUPPORTED_MAJOR_VERSION, UnsupportedVersion(version)))]
     version: [u16; 4],
+    /// Of unknown meaning, and 0 in every file seen so far.
     unused: [u8; 1],
     headers: SizedVec<u16, AchievementHeader>,
+    #[br(parse_with = read_contents)]
     contents: SizedVec<u32, AchievementContent>,
     #[br(parse_with = until_eof)]
     tracked: Vec<u16>,
 }
 
+/// Read the progress entries, marking errors in an entry as being in that
+/// achievement so that they can tell which one failed to parse.
+#[binrw::parser(reader, endian)]
+fn read_contents() -> BinResult<SizedVec<u32, AchievementContent>> {
+    SizedVec::read_options(reader, endian, ()).map_err(|err| match err {
+        binrw::Error::Custom { pos, err } => match err.downcast::<ElementError>() {
+            Ok(element) => binrw::Error::Custom {
+                pos,
+                err: Box::new(ElementError {
+                    is_achievement: true,
+                    ..*element
+                }),
+            },
+            Err(err) => binrw::Error::Custom { pos, err },
+        },
+        err => err,
+    })
+}
+
 impl AchievementsDat {
-    pub fn delete(mut self, id: &str) -> Self {
+    /// Parse a little-endian file, collecting warnings about anything
+    /// suspicious that did not prevent parsing.
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<ParseOutcome, Error> {
+        let data = Self::read_le(reader)?;
+        let warnings = data.warnings();
+        Ok(ParseOutcome { data, warnings })
+    }
+
+    /// Anything suspicious about the file that does not prevent parsing or
+    /// serializing it.
+    pub fn warnings(&self) -> Vec<Warning> {
+        warnings::collect(self)
+    }
+
+    /// Fix inconsistencies between the headers, the tracked list and the
+    /// contents: register unregistered achievements, drop duplicate
+    /// registrations, give achievements sharing a header index unique ones,
+    /// and drop dangling and duplicate tracked indices. Returns the warnings
+    /// that this resolved; anything else, such as duplicate content entries,
+    /// is left as it is.
+    pub fn repair(&mut self) -> Result<Vec<Warning>, String> {
+        repair::repair(self)
+    }
+
+    /// Game version that wrote the file, as major, minor, patch and build.
+    pub fn version(&self) -> [u16; 4] {
+        self.version
+    }
+
+    /// The byte after the version, of unknown meaning. It has been 0 in every
+    /// file seen so far.
+    pub fn unused_byte(&self) -> u8 {
+        self.unused[0]
+    }
+
+    /// Achievement types and IDs registered in the file, with the indices
+    /// that [Self::tracked] refers to.
+    pub fn headers(&self) -> &[AchievementHeader] {
+        &self.headers
+    }
+
+    /// Progress entries, in file order.
+    pub fn contents(&self) -> &[AchievementContent] {
+        &self.contents
+    }
+
+    /// Header indices of the achievements tracked in the game's UI.
+    pub fn tracked(&self) -> &[u16] {
+        &self.tracked
+    }
+
+    /// IDs of the tracked achievements, in order, or the index for tracked
+    /// indices that no header has.
+    pub fn tracked_ids(&self) -> Vec<Result<&str, u16>> {
+        self.tracked
+            .iter()
+            .map(|index| {
+                self.headers
+                    .iter()
+                    .flat_map(|header| header.subobjects.iter())
+                    .find(|sub| sub.index == *index)
+                    .map(|sub| sub.id.as_ref())
+                    .ok_or(*index)
+            })
+            .collect()
+    }
+
+    /// Track the achievement with the given ID in the game's UI. Returns
+    /// whether it was not already tracked, and fails if no header has the ID.
+    pub fn track(&mut self, id: &str) -> Result<bool, String> {
+        entries::track(self, id)
+    }
+
+    /// Stop tracking the achievement with the given ID. Returns whether it
+    /// was tracked.
+    pub fn untrack(&mut self, id: &str) -> bool {
+        entries::untrack(self, id)
+    }
+
+    /// IDs in the file that are close to `id`, closest first, to suggest
+    /// when `id` is not found.
+    pub fn similar_ids(&self, id: &str) -> Vec<&str> {
+        suggest::similar_ids(self, id)
+    }
+
+    /// The ID in the file that equals `prefix`, or else the only one that
+    /// starts with it. Fails if there is no such ID or more than one.
+    pub fn resolve_prefix(&self, prefix: &str) -> Result<&str, String> {
+        suggest::resolve_prefix(self, prefix)
+    }
+
+    /// The progress entries of the given type, in file order.
+    pub fn iter_by_type<'a>(
+        &'a self,
+        typ: &'a AchievementType,
+    ) -> impl Iterator<Item = &'a AchievementContent> {
+        self.contents
+            .iter()
+            .filter(move |content| content.typ == *typ)
+    }
+
+    /// The progress entry with the given ID, if any.
+    pub fn get(&self, id: &str) -> Option<&AchievementContent> {
+        self.contents
+            .iter()
+            .find(|content| content.id.as_ref() == id)
+    }
+
+    /// Like [Self::get], for modifying the entry's progress in place.
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut AchievementContent> {
         self.contents
             .iter_mut()
-            .filter(|content| content.id.as_ref() == id)
-            .for_each(|content| {
-                content.progress.reset();
-            });
-        self
+            .find(|content| content.id.as_ref() == id)
+    }
+
+    /// Achievements added, removed or changed in `new` compared to `self`,
+    /// matched by ID.
+    pub fn diff<'a>(&'a self, new: &'a Self) -> Diff<'a> {
+        diff::diff(self, new)
+    }
+
+    /// Combine the progress from `other` into this file, keeping the higher
+    /// value of each field. Achievements only in `other` are copied over.
+    pub fn merge(&mut self, other: &Self) -> Result<MergeReport, String> {
+        merge::merge(self, other)
+    }
+
+    /// Human-readable summary of the file, for display.
+    pub fn dump(&self) -> Dump<'_> {
+        Dump {
+            data: self,
+            types: &[],
+        }
+    }
+
+    /// Sorted line-oriented text form of the file, for diffing.
+    pub fn canonical(&self) -> Canonical<'_> {
+        Canonical { data: self }
+    }
+
+    /// Serialize to a little-endian file in memory, so that nothing is
+    /// written anywhere if serialization fails partway. The result is parsed
+    /// back and compared to `self`, so that a file that would not read back
+    /// the same is never returned.
+    #[cfg(feature = "write")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Cursor::new(Vec::new());
+        self.write_le(&mut buf)?;
+        buf.set_position(0);
+        if Self::read_le(&mut buf)? != *self {
+            return Err(Error::RoundTrip);
+        }
+        Ok(buf.into_inner())
+    }
+
+    /// Reset the progress of the achievement with the given ID, returning
+    /// the number of entries reset (zero if there is no such achievement).
+    pub fn delete(&mut self, id: &str) -> usize {
+        self.delete_matching(&[Pattern::Exact(id.to_string())])
+    }
+
+    /// Like [Self::delete], for every achievement whose ID matches any of
+    /// `patterns`.
+    pub fn delete_matching(&mut self, patterns: &[Pattern]) -> usize {
+        self.delete_selected(|content| Pattern::any_matches(patterns, &content.id))
+    }
+
+    /// Like [Self::delete], for every achievement of the given type.
+    pub fn delete_of_type(&mut self, typ: &AchievementType) -> usize {
+        self.delete_selected(|content| content.typ == *typ)
+    }
+
+    /// Like [Self::delete], for every achievement for which `select` returns
+    /// true.
+    pub fn delete_selected(&mut self, select: impl Fn(&AchievementContent) -> bool) -> usize {
+        let mut count = 0;
+        for content in self.contents.iter_mut().filter(|content| select(content)) {
+            content.progress.reset();
+            count += 1;
+        }
+        count
+    }
+
+    /// Like [Self::delete], for every achievement in the file.
+    pub fn reset_all(&mut self) -> usize {
+        for content in self.contents.iter_mut() {
+            content.progress.reset();
+        }
+        self.contents.len()
+    }
+
+    /// Add a new achievement, registering it in the headers. Fails if an
+    /// achievement with the same ID already exists.
+    pub fn insert_entry(&mut self, content: AchievementContent) -> Result<(), String> {
+        entries::insert(self, content)
+    }
+
+    /// Remove the achievement with the given ID entirely, rather than just
+    /// resetting its progress like [Self::delete]. Unless there are more
+    /// entries with the same ID, it is also removed from the headers and
+    /// from the tracked achievements.
+    pub fn remove_entry(&mut self, id: &str) -> Option<AchievementContent> {
+        entries::remove(self, id)
+    }
+
+    /// Replace the achievement with the given ID by its copy in `from`, such
+    /// as an older backup, adding it if it is missing. Fails if `from` has no
+    /// such achievement.
+    pub fn restore(&mut self, from: &AchievementsDat, id: &str) -> Result<(), String> {
+        entries::restore(self, from, id)
+    }
+
+    /// Replace the progress of the achievement with the same ID as `content`,
+    /// or if it is missing or of another type, the whole entry, registered
+    /// at header index `index` if that is given and free.
+    pub fn put_entry(
+        &mut self,
+        content: AchievementContent,
+        index: Option<u16>,
+    ) -> Result<(), String> {
+        entries::put(self, content, index)
+    }
+
+    /// Apply an assignment to a progress field of the achievement with the
+    /// given ID. Nothing is modified if it fails.
+    pub fn set(&mut self, id: &str, assignment: &Assignment) -> Result<(), String> {
+        let mut updated = Vec::new();
+        for (index, content) in self.contents.iter().enumerate() {
+            if content.id.as_ref() == id {
+                let mut progress = content.progress.clone();
+                assignment.apply(&mut progress)?;
+                updated.push((index, progress));
+            }
+        }
+        if updated.is_empty() {
+            return Err(format!("No achievement with ID {id}"));
+        }
+        for (index, progress) in updated {
+            self.contents.as_mut_slice()[index].progress = progress;
+        }
+        Ok(())
+    }
+
+    /// Apply an assignment to every achievement for which `select` returns
+    /// true. Achievements whose progress has no field of that name are
+    /// skipped; any other error aborts the whole operation without modifying
+    /// anything.
+    pub fn set_matching(
+        &mut self,
+        select: impl Fn(&AchievementContent) -> bool,
+        assignment: &Assignment,
+    ) -> Result<SetReport, String> {
+        let mut report = SetReport::default();
+        let mut updated = Vec::new();
+        for (index, content) in self.contents.iter().enumerate() {
+            if !select(content) {
+                continue;
+            }
+            if content.progress.field(&assignment.field).is_some() {
+                let mut progress = content.progress.clone();
+                assignment
+                    .apply(&mut progress)
+                    .map_err(|err| format!("{}: {err}", content.id()))?;
+                updated.push((index, progress));
+                report.modified.push(content.id.to_string());
+            } else {
+                report.skipped.push(content.id.to_string());
+            }
+        }
+        for (index, progress) in updated {
+            self.contents.as_mut_slice()[index].progress = progress;
+        }
+        Ok(report)
     }
 
     pub fn list(&self) -> BTreeSet<&SpaceOptimizedString> {
         self.contents.iter().map(|item| &item.id).collect()
     }
+
+    /// Like [Self::list], but only IDs that match any of `patterns`.
+    pub fn list_matching(&self, patterns: &[Pattern]) -> BTreeSet<&SpaceOptimizedString> {
+        self.contents
+            .iter()
+            .map(|item| &item.id)
+            .filter(|id| Pattern::any_matches(patterns, id))
+            .collect()
+    }
+
+    pub fn list_sorted(&self, key: &SortKey) -> Vec<&SpaceOptimizedString> {
+        self.sorted_contents(Some(key))
+            .into_iter()
+            .map(|item| &item.id)
+            .collect()
+    }
+
+    /// Contents sorted by `key`, then by ID.
+    pub fn sorted_contents(&self, key: Option<&SortKey>) -> Vec<&AchievementContent> {
+        let mut contents: Vec<_> = self.contents.iter().collect();
+        contents.sort_by(|a, b| {
+            key.map(|key| key.compare(a, b))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        contents
+    }
 }
 
-#[binrw]
-#[derive(Debug)]
+#[cfg_attr(feature = "write", binrw)]
+#[cfg_attr(not(feature = "write"), binread)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AchievementHeader {
-    typ: SpaceOptimizedString,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    typ: AchievementType,
     subobjects: SizedVec<u16, HeaderSubobject>,
 }
 
-#[binrw]
-#[derive(Debug)]
+impl AchievementHeader {
+    pub fn typ(&self) -> &AchievementType {
+        &self.typ
+    }
+
+    /// Achievements of this type, with their header indices.
+    pub fn subobjects(&self) -> &[HeaderSubobject] {
+        &self.subobjects
+    }
+}
+
+#[cfg_attr(feature = "write", binrw)]
+#[cfg_attr(not(feature = "write"), binread)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct HeaderSubobject {
     id: SpaceOptimizedString,
     index: u16,
 }
 
-#[binrw]
-#[derive(Debug)]
+impl HeaderSubobject {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+}
+
+#[cfg_attr(feature = "write", binrw)]
+#[cfg_attr(not(feature = "write"), binread)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "serde_support::RawContent"))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AchievementContent {
-    typ: SpaceOptimizedString,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    typ: AchievementType,
     id: SpaceOptimizedString,
-    #[br(args(typ.as_bytes()))]
+    #[br(args(&typ))]
     progress: AchievementProgress,
 }
 
-#[binrw]
-#[derive(Debug)]
-#[br(import(typ: &[u8]))]
+impl AchievementContent {
+    /// A new entry with zero progress. Fails if the type is unknown.
+    pub fn new(typ: AchievementType, id: &str) -> Result<Self, String> {
+        Ok(Self {
+            progress: AchievementProgress::default_for(&typ)
+                .ok_or_else(|| format!("Unknown achievement type {typ}"))?,
+            typ,
+            id: id.into(),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn typ(&self) -> &AchievementType {
+        &self.typ
+    }
+
+    pub fn progress(&self) -> &AchievementProgress {
+        &self.progress
+    }
+
+    pub fn progress_mut(&mut self) -> &mut AchievementProgress {
+        &mut self.progress
+    }
+
+    /// Whether the progress is that of a new entry of its type. False if the
+    /// type is unknown.
+    pub fn is_unstarted(&self) -> bool {
+        AchievementProgress::default_for(&self.typ).as_ref() == Some(&self.progress)
+    }
+}
+
+/// IDs of the achievements affected by [AchievementsDat::set_matching].
+#[derive(Debug, Default)]
+pub struct SetReport {
+    pub modified: Vec<String>,
+    /// Selected achievements whose progress has no field of the assigned name.
+    pub skipped: Vec<String>,
+}
+
+/// Achievement types that [AchievementProgress] can decode.
+pub const KNOWN_TYPES: &[AchievementType] = &[
+    AchievementType::Achievement,
+    AchievementType::BuildEntity,
+    AchievementType::ChangeSurface,
+    AchievementType::CombatRobotCount,
+    AchievementType::CompleteObjective,
+    AchievementType::ConstructWithRobots,
+    AchievementType::CreatePlatform,
+    AchievementType::DeconstructWithRobots,
+    AchievementType::DeliverByRobots,
+    AchievementType::DepleteResource,
+    AchievementType::DestroyCliff,
+    AchievementType::DontBuildEntity,
+    AchievementType::DontCraftManually,
+    AchievementType::DontKillManually,
+    AchievementType::DontResearchBeforeResearching,
+    AchievementType::DontUseEntityInEnergyProduction,
+    AchievementType::EquipArmor,
+    AchievementType::FinishTheGame,
+    AchievementType::GroupAttack,
+    AchievementType::Kill,
+    AchievementType::ModuleTransfer,
+    AchievementType::PlaceEquipment,
+    AchievementType::PlayerDamaged,
+    AchievementType::Produce,
+    AchievementType::ProducePerHour,
+    AchievementType::Research,
+    AchievementType::ResearchWithSciencePack,
+    AchievementType::Shoot,
+    AchievementType::SpaceConnectionDistanceTraveled,
+    AchievementType::TrainPath,
+    AchievementType::UseEntityInEnergyProduction,
+    AchievementType::UseItem,
+];
+
+#[cfg_attr(feature = "write", binwrite)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(untagged))]
+#[derive(Clone, Debug)]
 pub enum AchievementProgress {
-    #[br(pre_assert(typ == b"achievement"))]
     Achievement,
-    #[br(pre_assert(typ == b"build-entity-achievement"))]
     BuildEntity([u8; 4]),
-    #[br(pre_assert(typ == b"change-surface-achievement"))]
     ChangeSurface([u8; 1]),
-    #[br(pre_assert(typ == b"combat-robot-count-achievement"))]
     CombatRobotCount(u32),
-    #[br(pre_assert(typ == b"complete-objective-achievement"))]
     CompleteObjective,
-    #[br(pre_assert(typ == b"construct-with-robots-achievement"))]
-    ConstructWithRobots { constructed: u32, unknown: [u8; 4] },
-    #[br(pre_assert(typ == b"create-platform-achievement"))]
+    ConstructWithRobots {
+        constructed: u32,
+        unknown: [u8; 4],
+    },
     CreatePlatform([u8; 4]),
-    #[br(pre_assert(typ == b"deconstruct-with-robots-achievement"))]
-    DeconstructWithRobots { deconstructed: u32 },
-    #[br(pre_assert(typ == b"deliver-by-robots-achievement"))]
+    DeconstructWithRobots {
+        deconstructed: u32,
+    },
     DeliverByRobots([u8; 4]),
-    #[br(pre_assert(typ == b"deplete-resource-achievement"))]
     DepleteResource([u8; 4]),
-    #[br(pre_assert(typ == b"destroy-cliff-achievement"))]
     DestroyCliff([u8; 4]),
-    #[br(pre_assert(typ == b"dont-build-entity-achievement"))]
     DontBuildEntity([u8; 5]),
-    #[br(pre_assert(typ == b"dont-craft-manually-achievement"))]
     DontCraftManually([u8; 4]),
     /// Unknown format
-    #[br(pre_assert(typ == b"dont-kill-manually-achievement"))]
     DontKillManually([u8; 0]),
     /// Unknown format
-    #[br(pre_assert(typ == b"dont-research-before-researching-achievement"))]
     DontResearchBeforeResearching([u8; 0]),
-    #[br(pre_assert(typ == b"dont-use-entity-in-energy-production-achievement"))]
-    DontUseEntityInEnergyProduction { max_j_per_h: f64 },
-    #[br(pre_assert(typ == b"equip-armor-achievement"))]
+    DontUseEntityInEnergyProduction {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "serde_support::serialize_f64")
+        )]
+        max_j_per_h: f64,
+    },
     EquipArmor([u8; 4]),
-    #[br(pre_assert(typ == b"finish-the-game-achievement"))]
     FinishTheGame([u8; 4]),
-    #[br(pre_assert(typ == b"group-attack-achievement"))]
     GroupAttack([u8; 4]),
-    #[br(pre_assert(typ == b"kill-achievement"))]
-    Kill { max_killed: f64 },
-    #[br(pre_assert(typ == b"module-transfer-achievement"))]
+    Kill {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "serde_support::serialize_f64")
+        )]
+        max_killed: f64,
+    },
     ModuleTransfer([u8; 4]),
-    #[br(pre_assert(typ == b"place-equipment-achievement"))]
     PlaceEquipment([u8; 4]),
-    #[br(pre_assert(typ == b"player-damaged-achievement"))]
-    PlayerDamaged { max_damage: f32, survived: u8 },
-    #[br(pre_assert(typ == b"produce-achievement"))]
-    Produce { produced: f64 },
-    #[br(pre_assert(typ == b"produce-per-hour-achievement"))]
-    ProducePerHour { max_per_h: f64 },
-    #[br(pre_assert(typ == b"research-achievement"))]
+    PlayerDamaged {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "serde_support::serialize_f32")
+        )]
+        max_damage: f32,
+        survived: u8,
+    },
+    Produce {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "serde_support::serialize_f64")
+        )]
+        produced: f64,
+    },
+    ProducePerHour {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "serde_support::serialize_f64")
+        )]
+        max_per_h: f64,
+    },
     Research,
-    #[br(pre_assert(typ == b"research-with-science-pack-achievement"))]
     ResearchWithSciencePack([u8; 4]),
-    #[br(pre_assert(typ == b"shoot-achievement"))]
     Shoot([u8; 4]),
-    #[br(pre_assert(typ == b"space-connection-distance-traveled-achievement"))]
     SpaceConnectionDistanceTraveled([u8; 4]),
-    #[br(pre_assert(typ == b"train-path-achievement"))]
-    TrainPath { longest_path: f64 },
-    #[br(pre_assert(typ == b"use-entity-in-energy-production-achievement"))]
+    TrainPath {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "serde_support::serialize_f64")
+        )]
+        longest_path: f64,
+    },
     UseEntityInEnergyProduction([u8; 5]),
-    #[br(pre_assert(typ == b"use-item-achievement"))]
     UseItem([u8; 4]),
 }
 
+impl BinRead for AchievementProgress {
+    type Args<'a> = (&'a AchievementType,);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        (typ,): Self::Args<'_>,
+    ) -> BinResult<Self> {
+        fn read<T, R>(reader: &mut R, endian: Endian) -> BinResult<T>
+        where
+            for<'a> T: BinRead<Args<'a> = ()>,
+            R: Read + Seek,
+        {
+            T::read_options(reader, endian, ())
+        }
+
+        use AchievementProgress::*;
+        use AchievementType as T;
+        Ok(match typ {
+            T::Achievement => Achievement,
+            T::BuildEntity => BuildEntity(read(reader, endian)?),
+            T::ChangeSurface => ChangeSurface(read(reader, endian)?),
+            T::CombatRobotCount => CombatRobotCount(read(reader, endian)?),
+            T::CompleteObjective => CompleteObjective,
+            T::ConstructWithRobots => ConstructWithRobots {
+                constructed: read(reader, endian)?,
+                unknown: read(reader, endian)?,
+            },
+            T::CreatePlatform => CreatePlatform(read(reader, endian)?),
+            T::DeconstructWithRobots => DeconstructWithRobots {
+                deconstructed: read(reader, endian)?,
+            },
+            T::DeliverByRobots => DeliverByRobots(read(reader, endian)?),
+            T::DepleteResource => DepleteResource(read(reader, endian)?),
+            T::DestroyCliff => DestroyCliff(read(reader, endian)?),
+            T::DontBuildEntity => DontBuildEntity(read(reader, endian)?),
+            T::DontCraftManually => DontCraftManually(read(reader, endian)?),
+            T::DontKillManually => DontKillManually(read(reader, endian)?),
+            T::DontResearchBeforeResearching => {
+                DontResearchBeforeResearching(read(reader, endian)?)
+            }
+            T::DontUseEntityInEnergyProduction => DontUseEntityInEnergyProduction {
+                max_j_per_h: read(reader, endian)?,
+            },
+            T::EquipArmor => EquipArmor(read(reader, endian)?),
+            T::FinishTheGame => FinishTheGame(read(reader, endian)?),
+            T::GroupAttack => GroupAttack(read(reader, endian)?),
+            T::Kill => Kill {
+                max_killed: read(reader, endian)?,
+            },
+            T::ModuleTransfer => ModuleTransfer(read(reader, endian)?),
+            T::PlaceEquipment => PlaceEquipment(read(reader, endian)?),
+            T::PlayerDamaged => PlayerDamaged {
+                max_damage: read(reader, endian)?,
+                survived: read(reader, endian)?,
+            },
+            T::Produce => Produce {
+                produced: read(reader, endian)?,
+            },
+            T::ProducePerHour => ProducePerHour {
+                max_per_h: read(reader, endian)?,
+            },
+            T::Research => Research,
+            T::ResearchWithSciencePack => ResearchWithSciencePack(read(reader, endian)?),
+            T::Shoot => Shoot(read(reader, endian)?),
+            T::SpaceConnectionDistanceTraveled => {
+                SpaceConnectionDistanceTraveled(read(reader, endian)?)
+            }
+            T::TrainPath => TrainPath {
+                longest_path: read(reader, endian)?,
+            },
+            T::UseEntityInEnergyProduction => UseEntityInEnergyProduction(read(reader, endian)?),
+            T::UseItem => UseItem(read(reader, endian)?),
+            T::Other(name) => {
+                return Err(binrw::Error::Custom {
+                    pos: reader.stream_position()?,
+                    err: Box::new(UnknownType(name.clone())),
+                });
+            }
+        })
+    }
+}
+
+/// Equal if of the same type with bitwise equal fields, so that unlike the
+/// floating-point values themselves, NaN progress equals itself.
+impl PartialEq for AchievementProgress {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && self
+                .fields()
+                .iter()
+                .zip(other.fields().iter())
+                .all(|((_, a), (_, b))| a.total_cmp(b) == Ordering::Equal)
+    }
+}
+
 impl AchievementProgress {
     fn reset(&mut self) {
         use AchievementProgress::*;
@@ -168,8 +800,9 @@ impl AchievementProgress {
             DestroyCliff(..) => DestroyCliff(Default::default()),
             DontBuildEntity(..) => DontBuildEntity(Default::default()),
             DontCraftManually(..) => DontCraftManually(Default::default()),
-            DontKillManually(..) => todo!(),
-            DontResearchBeforeResearching(..) => todo!(),
+            // No payload has been seen for these, so there is nothing to reset
+            DontKillManually(..) => DontKillManually(Default::default()),
+            DontResearchBeforeResearching(..) => DontResearchBeforeResearching(Default::default()),
             DontUseEntityInEnergyProduction { .. } => DontUseEntityInEnergyProduction {
                 max_j_per_h: Default::default(),
             },
@@ -205,3 +838,38 @@ impl AchievementProgress {
         };
     }
 }
+
+/// Files and entries to test with.
+#[cfg(test)]
+pub(crate) mod test_data {
+    use super::*;
+
+    /// A file from game version 2.0.72 with `contents`, each registered in
+    /// the headers.
+    pub(crate) fn file(contents: impl IntoIterator<Item = AchievementContent>) -> AchievementsDat {
+        let mut data = AchievementsDat {
+            version: [2, 0, 72, 0],
+            unused: [0],
+            headers: Vec::new().try_into().unwrap(),
+            contents: Vec::new().try_into().unwrap(),
+            tracked: Vec::new(),
+        };
+        for content in contents {
+            data.insert_entry(content).unwrap();
+        }
+        data
+    }
+
+    /// An entry of type `typ` with `fields` set and all others zero.
+    pub(crate) fn entry(
+        typ: AchievementType,
+        id: &str,
+        fields: &[(&str, FieldValue)],
+    ) -> AchievementContent {
+        let mut content = AchievementContent::new(typ, id).unwrap();
+        for (name, value) in fields {
+            content.progress.set_field(name, *value).unwrap();
+        }
+        content
+    }
+}
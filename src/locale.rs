@@ -0,0 +1,110 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Achievement titles and descriptions from Factorio's locale files, such as
+//! `data/base/locale/en/base.cfg`:
+//!
+//! ```ini
+//! [achievement-name]
+//! mass-production-1=Mass production 1
+//!
+//! [achievement-description]
+//! mass-production-1=Produce __1__ __2__.
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default)]
+pub struct Locale {
+    names: BTreeMap<String, String>,
+    descriptions: BTreeMap<String, String>,
+}
+
+impl Locale {
+    /// Read the `*.cfg` files of `language` from each of `dirs`, which are
+    /// locale directories with one subdirectory per language, such as
+    /// `data/base/locale`. Later directories override earlier ones.
+    pub fn load(dirs: &[impl AsRef<Path>], language: &str) -> std::io::Result<Self> {
+        let mut locale = Self::default();
+        for dir in dirs {
+            let dir = dir.as_ref().join(language);
+            let mut paths: Vec<_> = std::fs::read_dir(&dir)
+                .map_err(|err| {
+                    std::io::Error::new(err.kind(), format!("{}: {err}", dir.display()))
+                })?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<_, _>>()?;
+            paths.retain(|path| path.extension().is_some_and(|ext| ext == "cfg"));
+            paths.sort();
+            for path in paths {
+                let text = std::fs::read_to_string(&path).map_err(|err| {
+                    std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: {err}", path.display()),
+                    )
+                })?;
+                locale.parse(&text);
+            }
+        }
+        Ok(locale)
+    }
+
+    fn parse(&mut self, text: &str) {
+        let mut section = None;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with([';', '#']) {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = match name {
+                    "achievement-name" => Some(&mut self.names),
+                    "achievement-description" => Some(&mut self.descriptions),
+                    _ => None,
+                };
+            } else if let Some(entries) = section.as_mut()
+                && let Some((key, value)) = line.split_once('=')
+            {
+                entries.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+
+    /// The title of the achievement with ID `id`, if any.
+    pub fn name(&self, id: &str) -> Option<&str> {
+        self.names.get(id).map(String::as_str)
+    }
+
+    /// The description of the achievement with ID `id`, if any. Parameters
+    /// such as `__1__` are left as they are.
+    pub fn description(&self, id: &str) -> Option<&str> {
+        self.descriptions.get(id).map(String::as_str)
+    }
+
+    /// The title and description of the achievement with ID `id`, as in
+    /// "Mass production 1: Produce __1__ __2__.", if it has either.
+    pub fn describe(&self, id: &str) -> Option<String> {
+        match (self.name(id), self.description(id)) {
+            (Some(name), Some(description)) => Some(format!("{name}: {description}")),
+            (Some(text), None) | (None, Some(text)) => Some(text.to_string()),
+            (None, None) => None,
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::env;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// File names Factorio uses for achievement progress, in order of preference.
+pub const FILE_NAMES: &[&str] = &["achievements.dat", "achievements-modded.dat"];
+
+/// Standard Factorio user data directories on this platform, whether or not
+/// they exist. Both the standalone and Steam versions of the game use these.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    if let Some(appdata) = env::var_os("APPDATA") {
+        dirs.push(PathBuf::from(appdata).join("Factorio"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+
+        #[cfg(target_os = "macos")]
+        dirs.push(home.join("Library/Application Support/factorio"));
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            dirs.push(home.join(".factorio"));
+            // Steam installed through Flatpak
+            dirs.push(home.join(".var/app/com.valvesoftware.Steam/.factorio"));
+        }
+    }
+
+    dirs
+}
+
+/// Existing achievements files in the standard locations, in order of
+/// preference: by directory in [data_dirs] order, then by [FILE_NAMES] order.
+pub fn find_achievements_files() -> Vec<PathBuf> {
+    data_dirs()
+        .into_iter()
+        .flat_map(|dir| FILE_NAMES.iter().map(move |name| dir.join(name)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Factorio's Steam app ID, which names its directory in Steam's per-account
+/// `userdata` directories.
+const STEAM_APP_ID: &str = "427520";
+
+/// Difference between a 64-bit Steam ID and the 32-bit account ID that names
+/// the account's `userdata` directory.
+const STEAM_ID64_BASE: u64 = 76561197960265728;
+
+/// Standard Steam installation directories on this platform, whether or not
+/// they exist.
+pub fn steam_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    if let Some(program_files) = env::var_os("ProgramFiles(x86)") {
+        dirs.push(PathBuf::from(program_files).join("Steam"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+
+        #[cfg(target_os = "macos")]
+        dirs.push(home.join("Library/Application Support/Steam"));
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            dirs.push(home.join(".local/share/Steam"));
+            dirs.push(home.join(".steam/steam"));
+            // Steam installed through Flatpak
+            dirs.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        }
+    }
+
+    dirs
+}
+
+/// Existing Factorio Steam Cloud directories, `userdata/<steamid>/427520/remote`,
+/// of all Steam accounts on this computer, as pairs of account ID and
+/// directory, sorted by account ID.
+pub fn steam_remote_dirs() -> Vec<(String, PathBuf)> {
+    let mut found: Vec<(String, PathBuf)> = Vec::new();
+    for userdata in steam_dirs().into_iter().map(|dir| dir.join("userdata")) {
+        let Ok(accounts) = userdata.read_dir() else {
+            continue;
+        };
+        for account in accounts.flatten() {
+            let id = account.file_name().to_string_lossy().into_owned();
+            let remote = account.path().join(STEAM_APP_ID).join("remote");
+            // ~/.steam/steam is usually a link to one of the other directories
+            if id.parse::<u32>().is_ok()
+                && remote.is_dir()
+                && !found.iter().any(|(found_id, _)| *found_id == id)
+            {
+                found.push((id, remote));
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// The Factorio Steam Cloud directory of the Steam account `steam_id`, given
+/// either as the account ID that names its `userdata` directory or as a
+/// 64-bit Steam ID, or of the only account that has one if `steam_id` is
+/// [None].
+pub fn steam_remote_dir(steam_id: Option<&str>) -> Result<PathBuf, String> {
+    let dirs = steam_remote_dirs();
+    let ids = || {
+        dirs.iter()
+            .map(|(id, _)| id.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match steam_id {
+        Some(steam_id) => {
+            let account_id = match steam_id.parse::<u64>() {
+                Ok(id64) if id64 > STEAM_ID64_BASE => (id64 - STEAM_ID64_BASE).to_string(),
+                _ => steam_id.to_string(),
+            };
+            dirs.iter()
+                .find(|(id, _)| *id == account_id)
+                .map(|(_, dir)| dir.clone())
+                .ok_or_else(|| match dirs.as_slice() {
+                    [] => format!("No Factorio Steam Cloud directory found for Steam ID {steam_id}"),
+                    _ => format!(
+                        "No Factorio Steam Cloud directory found for Steam ID {steam_id}; found ones for: {}",
+                        ids()
+                    ),
+                })
+        }
+        None => match dirs.as_slice() {
+            [] => Err(format!(
+                "No Factorio Steam Cloud directory found in: {}",
+                steam_dirs()
+                    .iter()
+                    .map(|dir| dir.join("userdata").display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            [(_, dir)] => Ok(dir.clone()),
+            _ => Err(format!(
+                "Several Steam accounts have Factorio Steam Cloud directories: {}; choose one with --steam-id",
+                ids()
+            )),
+        },
+    }
+}
+
+/// Whether `path` is in a Factorio Steam Cloud directory, where Steam may
+/// replace it with its cloud copy.
+pub fn is_steam_cloud(path: &Path) -> bool {
+    let under_userdata = |path: &Path| {
+        let components: Vec<Component> = path.components().collect();
+        components.windows(3).any(|window| {
+            window[0].as_os_str() == "userdata"
+                && window[1]
+                    .as_os_str()
+                    .to_string_lossy()
+                    .parse::<u32>()
+                    .is_ok()
+                && window[2].as_os_str() == STEAM_APP_ID
+        })
+    };
+    under_userdata(path) || path.canonicalize().is_ok_and(|path| under_userdata(&path))
+}
+
+/// Directory for this tool's own files, such as the usage statistics, in
+/// the standard configuration location for this platform.
+pub fn config_dir() -> Option<PathBuf> {
+    const NAME: &str = "factorio-achievements-editor";
+
+    #[cfg(target_os = "windows")]
+    return env::var_os("APPDATA").map(|appdata| PathBuf::from(appdata).join(NAME));
+
+    #[cfg(target_os = "macos")]
+    return env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join("Library/Application Support")
+            .join(NAME)
+    });
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join(NAME))
+}
@@ -14,67 +14,1907 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-#[cfg(debug_assertions)]
+mod backup;
+mod discover;
+mod editor;
+mod header;
+mod journal;
+mod locale;
+mod modpack;
+mod prototypes;
+mod rows;
+mod running;
+mod split;
+mod stats;
+mod summary;
+#[cfg(feature = "tui")]
+mod tui;
+mod usage;
+#[cfg(feature = "watch")]
+mod watch;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Cursor;
+use std::io::ErrorKind;
 use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
 
-use binrw::BinRead;
-use binrw::BinResult;
-use binrw::BinWrite;
-use binrw::io::NoSeek;
+use clap::Args;
+use clap::CommandFactory;
+use clap::FromArgMatches;
 use clap::Parser;
 use clap::Subcommand;
+use clap::ValueEnum;
+use factorio_achievements_editor::AchievementContent;
+use factorio_achievements_editor::AchievementType;
 use factorio_achievements_editor::AchievementsDat;
+use factorio_achievements_editor::Assignment;
+use factorio_achievements_editor::Edit;
+use factorio_achievements_editor::Error;
+use factorio_achievements_editor::FileSession;
+use factorio_achievements_editor::KNOWN_TYPES;
+use factorio_achievements_editor::LATEST_VERIFIED_VERSION;
+use factorio_achievements_editor::MIN_SUPPORTED_MAJOR_VERSION;
+use factorio_achievements_editor::ParseOutcome;
+use factorio_achievements_editor::Pattern;
+use factorio_achievements_editor::Query;
+use factorio_achievements_editor::SortKey;
+use factorio_achievements_editor::Template;
+use factorio_achievements_editor::VersionSupport;
+use factorio_achievements_editor::locate;
+use regex::Regex;
+
+use crate::discover::Layout;
+use crate::editor::DocFormat;
+use crate::locale::Locale;
+use crate::modpack::ModpackMeta;
+use crate::prototypes::Prototypes;
+use crate::rows::RowFormat;
+use crate::summary::Matches;
+use crate::summary::Summary;
 
 #[derive(Debug, Parser)]
 #[command(version, about)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+
+    /// Show the labels, categories and targets of achievements from this TOML file, such as one shipped by a modpack
+    #[arg(long, global = true, value_name = "FILE")]
+    modpack_meta: Option<PathBuf>,
+
+    /// Show progress against the goals in this prototype dump, as written by `factorio --dump-data`
+    #[arg(long, global = true, value_name = "FILE")]
+    prototypes: Option<PathBuf>,
+
+    /// Show achievement titles and descriptions from this Factorio locale directory, such as data/base/locale; may be given more than once
+    #[arg(long, global = true, value_name = "DIR")]
+    locale: Vec<PathBuf>,
+
+    /// Language of the --locale files to read
+    #[arg(long, global = true, value_name = "LANG", default_value = "en")]
+    language: String,
 }
 
 #[derive(Debug, Subcommand)]
 enum Command {
-    /// (Default) Parse standard input and dump contents to standard error
-    Dump,
+    /// (Default) Parse the input and dump contents to standard error
+    Dump {
+        #[command(flatten)]
+        input: Input,
+
+        /// Only show achievements of this type, e.g. "kill-achievement"; may be given more than once
+        #[arg(id = "types", long = "type", value_name = "TYPE")]
+        types: Vec<AchievementType>,
 
-    /// Delete the achivement with the given ID, and print the edited file to standard output
+        /// Print one row per achievement to standard output in this format instead
+        #[arg(long, value_enum)]
+        format: Option<RowFormat>,
+    },
+
+    /// Delete the achivements with the given IDs, and print the edited file to standard output
     Delete {
-        /// The achievement to delete
+        /// The achievement to delete, as an ID or a glob pattern such as "produce-*"
+        #[arg(value_name = "ID", required_unless_present_any = ["more_ids", "types"])]
+        id: Option<String>,
+
+        /// Another achievement to delete; may be given more than once
+        #[arg(id = "more_ids", long = "id", value_name = "ID")]
+        more_ids: Vec<String>,
+
+        /// Only delete achievements of this type, e.g. "dont-craft-manually-achievement", or all of them if no IDs are given; may be given more than once
+        #[arg(id = "types", long = "type", value_name = "TYPE")]
+        types: Vec<AchievementType>,
+
+        /// Match IDs with regular expressions instead of glob patterns
+        #[arg(long)]
+        regex: bool,
+
+        /// Also accept unambiguous prefixes of IDs, e.g. "lazy" for "lazy-bastard"
+        #[arg(long, conflicts_with = "regex")]
+        fuzzy: bool,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Remove the achievements with the given IDs from the file entirely, instead of just resetting them, and print the edited file to standard output
+    Remove {
+        /// The ID of the achievement to remove
         #[arg(value_name = "ID")]
         id: String,
+
+        /// Another achievement to remove; may be given more than once
+        #[arg(id = "more_ids", long = "id", value_name = "ID")]
+        more_ids: Vec<String>,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
     },
 
-    /// List achivement IDs present in standard input
-    List,
+    /// Add a new achievement with no progress, and print the edited file to standard output
+    Add {
+        /// The type of the new achievement, e.g. "kill-achievement"
+        #[arg(value_name = "TYPE")]
+        typ: AchievementType,
+
+        /// The ID of the new achievement
+        #[arg(value_name = "ID")]
+        id: String,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Track the given achievements in the game's UI, and print the edited file to standard output
+    Track {
+        /// The ID of the achievement to track
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// Another achievement to track; may be given more than once
+        #[arg(id = "more_ids", long = "id", value_name = "ID")]
+        more_ids: Vec<String>,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Stop tracking the given achievements in the game's UI, and print the edited file to standard output
+    Untrack {
+        /// The ID of the achievement to stop tracking
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// Another achievement to stop tracking; may be given more than once
+        #[arg(id = "more_ids", long = "id", value_name = "ID")]
+        more_ids: Vec<String>,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// List the IDs of the achievements tracked in the game's UI
+    Tracked {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Delete all achievements, and print the edited file to standard output
+    ResetAll {
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Set a progress field of the selected achievements, and print the edited file to standard output
+    Set {
+        /// The achievement to edit, or a glob pattern such as "produce-*" or "*"
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// The field and new value, e.g. "produced=1000", "produced+=1000" or "produced*=2"
+        #[arg(value_name = "FIELD=VALUE")]
+        assignment: Assignment,
+
+        /// Only edit achievements of this type, e.g. "kill-achievement"
+        #[arg(id = "typ", long = "type", value_name = "TYPE")]
+        typ: Option<AchievementType>,
+
+        /// Match ID with a regular expression instead of a glob pattern
+        #[arg(long)]
+        regex: bool,
+
+        /// Also accept an unambiguous prefix of the ID, e.g. "lazy" for "lazy-bastard"
+        #[arg(long, conflicts_with = "regex")]
+        fuzzy: bool,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Edit the selected achievement, or the whole file, in $EDITOR, and print the edited file to standard output
+    Edit {
+        /// The achievement to edit; all of the file if not given
+        #[arg(value_name = "ID")]
+        id: Option<String>,
+
+        /// Format of the document to edit
+        #[arg(long, value_enum, default_value_t = DocFormat::Json)]
+        format: DocFormat,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Merge the progress from another file, keeping the higher value of each field, and print the result to standard output
+    Merge {
+        /// The file to merge progress from
+        #[arg(value_name = "OTHER")]
+        other: PathBuf,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// For contributors: compare the structure of files from two game versions, and print a skeleton of the parser changes that new or changed achievement types need
+    Discover {
+        /// A file from the game version the parser already supports
+        #[arg(long, value_name = "FILE")]
+        old: PathBuf,
+
+        /// A file from the new game version
+        #[arg(long, value_name = "FILE")]
+        new: PathBuf,
+    },
+
+    /// Restore the given achievements, or the whole file, from a backup, and print the result to standard output
+    Restore {
+        /// The backup to restore from
+        #[arg(long, value_name = "BACKUP", required_unless_present = "snapshot")]
+        from: Option<PathBuf>,
+
+        /// Restore from this snapshot in the backup directory, as listed by backup --list, or "latest"
+        #[arg(long, value_name = "NAME", conflicts_with = "from")]
+        snapshot: Option<String>,
+
+        /// The directory to find --snapshot in, instead of "backups" in the configuration directory
+        #[arg(long, value_name = "DIR", requires = "snapshot")]
+        backup_dir: Option<PathBuf>,
+
+        /// The IDs of the achievements to restore, leaving the rest as they are; if none are given, the whole file is restored
+        #[arg(long = "id", value_name = "ID")]
+        ids: Vec<String>,
+
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Revert the most recent edit made to a file with --in-place, as recorded in its journal
+    Undo {
+        #[command(flatten)]
+        input: Input,
+
+        /// Print what undoing would change to standard error instead of writing the file
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write the file even if Factorio seems to be running
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Save a timestamped snapshot of the input in the backup directory, or list or prune the snapshots there
+    Backup {
+        #[command(flatten)]
+        input: Input,
+
+        /// The directory to keep snapshots in, instead of "backups" in the configuration directory
+        #[arg(long, value_name = "DIR")]
+        backup_dir: Option<PathBuf>,
+
+        /// List the snapshots, oldest first, instead of saving one
+        #[arg(long, conflicts_with_all = ["keep", "older_than"])]
+        list: bool,
+
+        /// Delete all but the newest N snapshots, instead of saving one
+        #[arg(long, value_name = "N")]
+        keep: Option<usize>,
+
+        /// Delete the snapshots older than this many days, instead of saving one
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+
+    /// Compare two files, and print the achievements added, removed or changed in NEW to standard error
+    Diff {
+        /// The file to compare against
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// The file whose changes to show
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+    },
+
+    /// Evaluate a query over the achievements, and print the result to standard output
+    Eval {
+        /// The query, e.g. "count where type=kill-achievement" or "max(progress.produced) where id=produce-*"
+        #[arg(value_name = "EXPR")]
+        query: Query,
+
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// List the achievements in --prototypes, or else in --modpack-meta, that have no entry in the input at all, as opposed to an entry with no progress
+    Missing {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Exit successfully if the input has an achievement with the given ID, and with status 1 otherwise
+    Has {
+        /// The achievement ID to look for
+        #[arg(value_name = "ID")]
+        id: String,
+
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Print the file version, the unused byte and the offsets and sizes of each section, and flag anything unexpected
+    Header {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Print the number of achievements in total, by type and with progress, and the size of each section
+    Stats {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Check that the input parses and re-serializes to exactly the same bytes
+    Validate {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Check that the headers, tracked achievements and progress entries are consistent with each other
+    Check {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Fix the problems reported by check where possible, and print the edited file to standard output
+    Repair {
+        #[command(flatten)]
+        input: Input,
+
+        #[command(flatten)]
+        output: Output,
+    },
+
+    /// Show the usage statistics kept on this computer, if enabled; they are never sent anywhere
+    Usage {
+        /// Start keeping statistics of the commands run, files edited and warnings seen
+        #[arg(long, conflicts_with_all = ["clear", "disable"])]
+        enable: bool,
+
+        /// Forget the statistics kept so far
+        #[arg(long, conflicts_with = "disable")]
+        clear: bool,
+
+        /// Stop keeping statistics, and delete them
+        #[arg(long)]
+        disable: bool,
+    },
+
+    /// Report whether files written by a game version are supported, and which achievement types are known
+    Supports {
+        /// The game version, e.g. "2.0.72"
+        #[arg(value_name = "GAME_VERSION", value_parser = parse_version)]
+        version: [u16; 4],
+    },
+
+    /// Print the progress changes to standard error each time the file is written, such as by the game during play, until interrupted
+    #[cfg(feature = "watch")]
+    Watch {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Browse and edit the file interactively, and save it in place on exit
+    #[cfg(feature = "tui")]
+    Tui {
+        #[command(flatten)]
+        input: Input,
+
+        /// When saving, apply the edits to the file's new contents without asking if another program changed it
+        #[arg(long)]
+        merge: bool,
+
+        /// Allow editing files from game versions newer than this tool was checked against
+        #[arg(long)]
+        allow_newer: bool,
+
+        /// Open the file even if Factorio seems to be running
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the parsed contents as JSON to standard output
+    ToJson {
+        #[command(flatten)]
+        input: Input,
+
+        /// Instead write one JSON file per achievement to DIR, as DIR/TYPE/ID.json, and the rest of the file to DIR/achievements.json
+        #[arg(long, value_name = "DIR")]
+        split_per_achievement: Option<PathBuf>,
+    },
+
+    /// Print the contents as sorted lines of text to standard output, for diffing and version control
+    Canonicalize {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Read JSON as printed by to-json, and print it as a binary file to standard output
+    FromJson {
+        #[command(flatten)]
+        input: Input,
+
+        /// Instead read a directory written by to-json --split-per-achievement
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["file", "file_flag", "auto", "steam"])]
+        split_per_achievement: Option<PathBuf>,
+    },
+
+    /// List achivement IDs present in the input
+    List {
+        #[command(flatten)]
+        input: Input,
+
+        /// Sort by a field instead of by ID, e.g. "progress.max_per_h desc"
+        #[arg(long, value_name = "EXPR")]
+        sort_by: Option<SortKey>,
+
+        /// Print one line per achievement to standard output, e.g. "{id}\t{progress.produced}"
+        #[arg(long)]
+        template: Option<Template>,
+
+        /// Also show each achievement's progress fields
+        #[arg(long, conflicts_with = "template")]
+        progress: bool,
+
+        /// Print one row per achievement to standard output in this format
+        #[arg(long, value_enum, conflicts_with_all = ["template", "progress"])]
+        format: Option<RowFormat>,
+
+        /// Only list achievements whose ID matches this glob pattern, e.g. "produce-*"
+        #[arg(long = "id", value_name = "PATTERN")]
+        ids: Vec<String>,
+
+        /// Match --id with regular expressions instead of glob patterns
+        #[arg(long, requires = "ids")]
+        regex: bool,
+
+        /// Only list achievements of this type, e.g. "kill-achievement"; may be given more than once
+        #[arg(id = "types", long = "type", value_name = "TYPE")]
+        types: Vec<AchievementType>,
+    },
 }
 
-fn main() -> BinResult<()> {
-    let cli = Cli::parse();
-    let mut stdin = std::io::stdin();
+impl Command {
+    /// The edit made by commands that edit the file, which sets `matches` for
+    /// commands that select achievements.
+    fn edit(&self, matches: &Matches) -> Result<Option<Edit>, Error> {
+        Ok(match self {
+            Self::Delete {
+                id,
+                more_ids,
+                types,
+                regex,
+                fuzzy,
+                ..
+            } => {
+                let ids: Vec<String> = id.iter().chain(more_ids).cloned().collect();
+                let types = types.clone();
+                let regex = *regex;
+                let fuzzy = *fuzzy;
+                let patterns = patterns(ids.clone(), regex)?;
+                let matches = matches.clone();
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    let patterns = if fuzzy {
+                        resolve_prefixes(&data, &ids)?
+                    } else {
+                        patterns.clone()
+                    };
+                    let unmatched: Vec<&str> = ids
+                        .iter()
+                        .zip(&patterns)
+                        .filter(|(_, pattern)| {
+                            !data
+                                .contents()
+                                .iter()
+                                .any(|content| pattern.matches(content.id()))
+                        })
+                        .map(|(id, _)| id.as_str())
+                        .collect();
+                    if !unmatched.is_empty() {
+                        let mut similar: Vec<&str> = if regex {
+                            Vec::new()
+                        } else {
+                            unmatched
+                                .iter()
+                                .flat_map(|id| data.similar_ids(id))
+                                .collect()
+                        };
+                        similar.dedup();
+                        return Err(format!(
+                            "No achievements matched {}{}",
+                            unmatched.join(", "),
+                            did_you_mean(&similar)
+                        ));
+                    }
+                    if let Some(typ) = types
+                        .iter()
+                        .find(|typ| data.iter_by_type(typ).next().is_none())
+                    {
+                        return Err(format!("No achievements of type {typ}"));
+                    }
+                    let deleted = if types.is_empty() {
+                        data.delete_matching(&patterns)
+                    } else if ids.is_empty() {
+                        types.iter().map(|typ| data.delete_of_type(typ)).sum()
+                    } else {
+                        data.delete_selected(|content| {
+                            types.contains(content.typ())
+                                && Pattern::any_matches(&patterns, content.id())
+                        })
+                    };
+                    matches.set(deleted, 0);
+                    Ok(data)
+                }))
+            }
 
-    let data = AchievementsDat::read_le(&mut NoSeek::new(&mut stdin))?;
+            Self::Remove { id, more_ids, .. } => {
+                let ids: Vec<String> = std::iter::once(id).chain(more_ids).cloned().collect();
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    for id in &ids {
+                        if data.get(id).is_none() {
+                            return Err(not_found(&data, id));
+                        }
+                        data.remove_entry(id);
+                    }
+                    Ok(data)
+                }))
+            }
 
-    match cli.command {
-        None | Some(Command::Dump) => {
-            dbg!(data);
+            Self::Add { typ, id, .. } => {
+                let content =
+                    AchievementContent::new(typ.clone(), id).map_err(std::io::Error::other)?;
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    data.insert_entry(content.clone())?;
+                    Ok(data)
+                }))
+            }
+
+            Self::Track { id, more_ids, .. } => {
+                let ids: Vec<String> = std::iter::once(id).chain(more_ids).cloned().collect();
+                let matches = matches.clone();
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    let mut skipped = 0;
+                    for id in &ids {
+                        if !data.track(id)? {
+                            eprintln!("Warning: {id} is already tracked");
+                            skipped += 1;
+                        }
+                    }
+                    matches.set(ids.len(), skipped);
+                    Ok(data)
+                }))
+            }
+
+            Self::Untrack { id, more_ids, .. } => {
+                let ids: Vec<String> = std::iter::once(id).chain(more_ids).cloned().collect();
+                let matches = matches.clone();
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    let mut skipped = 0;
+                    for id in &ids {
+                        if !data.untrack(id) {
+                            eprintln!("Warning: {id} is not tracked");
+                            skipped += 1;
+                        }
+                    }
+                    matches.set(ids.len(), skipped);
+                    Ok(data)
+                }))
+            }
+
+            Self::Repair { .. } => Some(Arc::new(|mut data: AchievementsDat| {
+                let repaired = data.repair()?;
+                for warning in &repaired {
+                    eprintln!("Repaired: {warning}");
+                }
+                for warning in data.warnings() {
+                    eprintln!("Warning: Not repaired: {warning}");
+                }
+                Ok(data)
+            })),
+
+            Self::ResetAll { .. } => Some(Arc::new(|mut data: AchievementsDat| {
+                data.reset_all();
+                Ok(data)
+            })),
+
+            Self::Set {
+                id,
+                assignment,
+                typ,
+                regex,
+                fuzzy,
+                ..
+            } => {
+                let patterns = patterns(vec![id.clone()], *regex)?;
+                let id = id.clone();
+                let regex = *regex;
+                let fuzzy = *fuzzy;
+                let typ = typ.clone();
+                let assignment = assignment.clone();
+                let matches = matches.clone();
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    let patterns = if fuzzy {
+                        resolve_prefixes(&data, std::slice::from_ref(&id))?
+                    } else {
+                        patterns.clone()
+                    };
+                    if !regex
+                        && !data
+                            .contents()
+                            .iter()
+                            .any(|content| Pattern::any_matches(&patterns, content.id()))
+                    {
+                        return Err(format!(
+                            "No achievements matched {id}{}",
+                            did_you_mean(&data.similar_ids(&id))
+                        ));
+                    }
+                    let select = |content: &AchievementContent| {
+                        typ.as_ref().is_none_or(|typ| content.typ() == typ)
+                            && Pattern::any_matches(&patterns, content.id())
+                    };
+                    let report = data.set_matching(select, &assignment)?;
+                    matches.set(
+                        report.modified.len() + report.skipped.len(),
+                        report.skipped.len(),
+                    );
+                    if !report.skipped.is_empty() {
+                        eprintln!(
+                            "Skipped {} achievements without a field named {}: {}",
+                            report.skipped.len(),
+                            assignment.field,
+                            report.skipped.join(", ")
+                        );
+                    }
+                    if report.modified.is_empty() {
+                        return Err("No achievements were modified".to_string());
+                    }
+                    eprintln!("Modified {} achievements", report.modified.len());
+                    Ok(data)
+                }))
+            }
+
+            Self::Restore {
+                from,
+                snapshot,
+                backup_dir,
+                ids,
+                ..
+            } => {
+                let from = match (from, snapshot) {
+                    (Some(from), _) => read_file(from)?,
+                    (None, Some(name)) => {
+                        read_file(&backup::find(&backup::dir(backup_dir.as_deref())?, name)?)?
+                    }
+                    (None, None) => unreachable!("required by clap"),
+                };
+                let ids = ids.clone();
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    if ids.is_empty() {
+                        return Ok(from.clone());
+                    }
+                    for id in &ids {
+                        if from.get(id).is_none() {
+                            return Err(format!(
+                                "No achievement with ID {id} in the backup{}",
+                                did_you_mean(&from.similar_ids(id))
+                            ));
+                        }
+                        data.restore(&from, id)?;
+                    }
+                    Ok(data)
+                }))
+            }
+
+            Self::Merge { other, .. } => {
+                let other = read_file(other)?;
+                Some(Arc::new(move |mut data: AchievementsDat| {
+                    let report = data.merge(&other)?;
+                    if !report.added.is_empty() {
+                        eprintln!(
+                            "Added {} achievements: {}",
+                            report.added.len(),
+                            report.added.join(", ")
+                        );
+                    }
+                    eprintln!("Updated {} achievements", report.updated.len());
+                    if !report.conflicts.is_empty() {
+                        eprintln!(
+                            "Warning: Kept {} conflicting achievements unchanged: {}",
+                            report.conflicts.len(),
+                            report.conflicts.join(", ")
+                        );
+                    }
+                    Ok(data)
+                }))
+            }
+
+            _ => None,
+        })
+    }
+
+    fn output(&self) -> Option<&Output> {
+        match self {
+            Self::Delete { output, .. }
+            | Self::Remove { output, .. }
+            | Self::Add { output, .. }
+            | Self::Track { output, .. }
+            | Self::Repair { output, .. }
+            | Self::Untrack { output, .. }
+            | Self::ResetAll { output, .. }
+            | Self::Set { output, .. }
+            | Self::Edit { output, .. }
+            | Self::Merge { output, .. }
+            | Self::Restore { output, .. } => Some(output),
+            _ => None,
+        }
+    }
+
+    fn input_format(&self) -> Format {
+        match self {
+            Self::FromJson { .. } => Format::Json,
+            Self::Dump { input, .. }
+            | Self::Validate { input }
+            | Self::ResetAll { input, .. }
+            | Self::Delete { input, .. }
+            | Self::ToJson { input, .. }
+            | Self::Eval { input, .. }
+            | Self::Tracked { input }
+            | Self::Has { input, .. }
+            | Self::Missing { input }
+            | Self::Header { input }
+            | Self::Stats { input }
+            | Self::Canonicalize { input }
+            | Self::Backup { input, .. }
+            | Self::Undo { input, .. }
+            | Self::Check { input }
+            | Self::List { input, .. }
+            | Self::Remove { input, .. }
+            | Self::Add { input, .. }
+            | Self::Track { input, .. }
+            | Self::Repair { input, .. }
+            | Self::Untrack { input, .. }
+            | Self::Set { input, .. }
+            | Self::Edit { input, .. }
+            | Self::Merge { input, .. }
+            | Self::Restore { input, .. } => input.flags.stdin_format,
+            Self::Diff { .. }
+            | Self::Discover { .. }
+            | Self::Supports { .. }
+            | Self::Usage { .. } => Format::Binary,
+            #[cfg(feature = "tui")]
+            Self::Tui { input, .. } => input.flags.stdin_format,
+            #[cfg(feature = "watch")]
+            Self::Watch { input } => input.flags.stdin_format,
         }
+    }
 
-        Some(Command::Delete { id }) => {
-            let data = data.delete(&id);
-            data.write_le(&mut NoSeek::new(&mut std::io::stdout()))?;
+    fn input_path(&self) -> std::io::Result<Option<PathBuf>> {
+        match self {
+            Self::Dump { input, .. }
+            | Self::Validate { input }
+            | Self::ResetAll { input, .. }
+            | Self::Delete { input, .. }
+            | Self::ToJson { input, .. }
+            | Self::FromJson { input, .. }
+            | Self::Eval { input, .. }
+            | Self::Tracked { input }
+            | Self::Has { input, .. }
+            | Self::Missing { input }
+            | Self::Header { input }
+            | Self::Stats { input }
+            | Self::Canonicalize { input }
+            | Self::Backup { input, .. }
+            | Self::Undo { input, .. }
+            | Self::Check { input }
+            | Self::List { input, .. }
+            | Self::Remove { input, .. }
+            | Self::Add { input, .. }
+            | Self::Track { input, .. }
+            | Self::Repair { input, .. }
+            | Self::Untrack { input, .. }
+            | Self::Set { input, .. }
+            | Self::Edit { input, .. }
+            | Self::Merge { input, .. }
+            | Self::Restore { input, .. } => input.path(),
+            Self::Diff { .. }
+            | Self::Discover { .. }
+            | Self::Supports { .. }
+            | Self::Usage { .. } => Ok(None),
+            #[cfg(feature = "tui")]
+            Self::Tui { input, .. } => input.path(),
+            #[cfg(feature = "watch")]
+            Self::Watch { input } => input.path(),
         }
+    }
+}
+
+#[derive(Debug, Args)]
+struct Input {
+    /// Read this file instead of standard input
+    #[arg(value_name = "FILE", conflicts_with_all = ["file_flag", "auto", "steam"])]
+    file: Option<PathBuf>,
+
+    #[command(flatten)]
+    flags: InputFlags,
+}
+
+/// Input options other than a positional `FILE`.
+#[derive(Debug, Args)]
+struct InputFlags {
+    /// Read this file instead of standard input
+    #[arg(id = "file_flag", long = "file", value_name = "FILE")]
+    file_flag: Option<PathBuf>,
+
+    /// Read the achievements file from the standard Factorio data directory
+    #[arg(long, conflicts_with = "file_flag")]
+    auto: bool,
+
+    /// Read the achievements file from Factorio's Steam Cloud directory
+    #[arg(long, conflicts_with_all = ["file_flag", "auto"])]
+    steam: bool,
+
+    /// With --steam, the Steam account to use if there are several
+    #[arg(long, value_name = "ID", requires = "steam")]
+    steam_id: Option<String>,
+
+    /// Format of the input file
+    #[arg(long, value_enum, default_value_t = Format::Binary)]
+    stdin_format: Format,
+}
+
+/// File formats for --stdin-format and --stdout-format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Format {
+    /// The binary achievements.dat format
+    Binary,
+    /// JSON as printed by to-json
+    Json,
+}
+
+/// Where commands that edit the file write the result.
+#[derive(Debug, Args)]
+struct Output {
+    /// Write the edited file back to the input file instead of to standard output
+    #[arg(long)]
+    in_place: bool,
+
+    /// With --in-place, apply the edits to the file's new contents if another program changed it after it was read
+    #[arg(long, requires = "in_place")]
+    merge: bool,
+
+    /// Format of the edited file printed to standard output
+    #[arg(long, value_enum, default_value_t = Format::Binary, conflicts_with = "in_place")]
+    stdout_format: Format,
+
+    /// Print what the edit would change to standard error instead of writing the edited file anywhere
+    #[arg(long, conflicts_with = "merge")]
+    dry_run: bool,
+
+    /// Allow editing files from game versions newer than this tool was checked against
+    #[arg(long)]
+    allow_newer: bool,
+
+    /// With --in-place, write the file even if Factorio seems to be running
+    #[arg(long, requires = "in_place")]
+    force: bool,
 
-        Some(Command::List) => {
-            dbg!(data.list());
+    /// With --in-place, save a snapshot of the file before writing it, in DIR or else in "backups" in the configuration directory
+    #[arg(long, value_name = "DIR", num_args = 0..=1, require_equals = true, requires = "in_place")]
+    backup: Option<Option<PathBuf>>,
+}
+
+impl Input {
+    fn path(&self) -> std::io::Result<Option<PathBuf>> {
+        match &self.file {
+            Some(file) => Ok(Some(file.clone())),
+            None => self.flags.path(),
+        }
+    }
+}
+
+impl InputFlags {
+    fn path(&self) -> std::io::Result<Option<PathBuf>> {
+        if self.auto {
+            let path = locate::find_achievements_files()
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "No achievements file found in: {}",
+                            locate::data_dirs()
+                                .iter()
+                                .map(|dir| dir.display().to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                })?;
+            eprintln!("Reading {}", path.display());
+            Ok(Some(path))
+        } else if self.steam {
+            let dir = locate::steam_remote_dir(self.steam_id.as_deref())
+                .map_err(|err| std::io::Error::new(ErrorKind::NotFound, err))?;
+            let path = locate::FILE_NAMES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        ErrorKind::NotFound,
+                        format!("No achievements file found in: {}", dir.display()),
+                    )
+                })?;
+            eprintln!("Reading {}", path.display());
+            Ok(Some(path))
+        } else {
+            Ok(self.file_flag.clone())
         }
     }
+}
 
-    #[cfg(debug_assertions)]
-    dbg!({
-        let mut buf = Vec::new();
-        stdin.read_to_end(&mut buf)?;
-        buf
-    });
+/// The exact IDs that `prefixes` are unambiguous prefixes of.
+fn resolve_prefixes(data: &AchievementsDat, prefixes: &[String]) -> Result<Vec<Pattern>, String> {
+    prefixes
+        .iter()
+        .map(|prefix| Ok(Pattern::Exact(data.resolve_prefix(prefix)?.to_string())))
+        .collect()
+}
+
+fn not_found(data: &AchievementsDat, id: &str) -> String {
+    format!(
+        "No achievement with ID {id}{}",
+        did_you_mean(&data.similar_ids(id))
+    )
+}
+
+fn did_you_mean(similar: &[&str]) -> String {
+    match similar {
+        [] => String::new(),
+        similar => format!("; did you mean {}?", similar.join(", ")),
+    }
+}
+
+/// Refuse to edit files from game versions newer than the tool was checked
+/// against, whose layout may have changed in ways it cannot tell, unless
+/// `allow_newer`.
+fn check_version(data: &AchievementsDat, allow_newer: bool) -> std::io::Result<()> {
+    if allow_newer || !matches!(VersionSupport::of(data.version()), VersionSupport::Newer) {
+        return Ok(());
+    }
+    let [major, minor, patch, build] = data.version();
+    let [verified_major, verified_minor] = LATEST_VERIFIED_VERSION;
+    Err(std::io::Error::other(format!(
+        "The file is from Factorio {major}.{minor}.{patch}.{build}, newer than {verified_major}.{verified_minor}, the latest version this tool was checked against; use --allow-newer to edit it anyway"
+    )))
+}
+
+/// Refuse to write `path` while the game is running, unless `force` is set.
+fn check_running(path: &Path, force: bool) -> std::io::Result<()> {
+    match running::factorio_running(path) {
+        Some(evidence) if !force => Err(std::io::Error::other(format!(
+            "Factorio seems to be running ({evidence}), and it overwrites {} when it exits, which would discard this edit; quit the game first, or use --force to write anyway",
+            path.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The edit made in the user's editor to the achievement `id`, or to the
+/// whole file if [None], or [None] if the document was saved unchanged.
+fn edit_in_editor(
+    path: Option<&Path>,
+    input_format: Format,
+    id: Option<&str>,
+    format: DocFormat,
+) -> Result<Option<Edit>, Error> {
+    let path = path.ok_or_else(|| {
+        std::io::Error::other("edit needs a file to edit, given by FILE, --file, --auto or --steam")
+    })?;
+    let input = std::fs::read(path)?;
+    let original = match input_format {
+        Format::Binary => AchievementsDat::parse(&mut Cursor::new(&input))?.data,
+        Format::Json => serde_json::from_slice(&input).map_err(std::io::Error::from)?,
+    };
+    match id {
+        Some(id) => {
+            let content = original
+                .get(id)
+                .ok_or_else(|| std::io::Error::other(not_found(&original, id)))?;
+            let edited = editor::edit(content, format, |text| {
+                editor::read_entry(format, text, content)
+            })?;
+            Ok(edited.map(|edited| -> Edit {
+                Arc::new(move |mut data: AchievementsDat| {
+                    data.put_entry(edited.clone(), None)?;
+                    Ok(data)
+                })
+            }))
+        }
+        None => {
+            let edited = editor::edit(&original, format, |text| {
+                let edited: AchievementsDat = format.read(text)?;
+                edited.to_bytes().map_err(|err| err.to_string())?;
+                Ok(edited)
+            })?;
+            Ok(edited.map(|edited| -> Edit {
+                Arc::new(move |data: AchievementsDat| {
+                    // Replacing the whole file would discard changes made
+                    // by another program while it was being edited.
+                    if data == original {
+                        Ok(edited.clone())
+                    } else {
+                        Err(
+                            "The file was changed by another program while it was being edited"
+                                .to_string(),
+                        )
+                    }
+                })
+            }))
+        }
+    }
+}
+
+/// Warn that Steam may replace `path` with its cloud copy, undoing the edit.
+fn warn_steam_cloud(path: &Path) {
+    if locate::is_steam_cloud(path) {
+        eprintln!(
+            "Warning: {} is in a Steam Cloud directory, and Steam may replace it with its cloud copy; disable Steam Cloud for Factorio to keep this edit",
+            path.display()
+        );
+    }
+}
+
+/// The target of each progress field in `data` that has one, from
+/// `--modpack-meta` or else from `--prototypes`.
+fn targets<'a>(
+    data: &'a AchievementsDat,
+    modpack: Option<&ModpackMeta>,
+    prototypes: Option<&Prototypes>,
+) -> BTreeMap<(&'a str, &'static str), f64> {
+    data.contents()
+        .iter()
+        .filter_map(|content| {
+            let (field, target) = modpack
+                .and_then(|modpack| modpack.target(content))
+                .or_else(|| prototypes?.target(content))?;
+            Some(((content.id(), field), target))
+        })
+        .collect()
+}
+
+fn print_dry_run(before: &AchievementsDat, after: &AchievementsDat) {
+    let diff = before.diff(after);
+    if diff.is_empty() {
+        eprintln!("Dry run: the edit would change no progress");
+    } else {
+        eprint!("{diff}");
+        eprintln!("Dry run: nothing was written");
+    }
+}
+
+fn patterns(ids: Vec<String>, regex: bool) -> std::io::Result<Vec<Pattern>> {
+    ids.into_iter()
+        .map(|id| {
+            if regex {
+                Regex::new(&id)
+                    .map(Pattern::Regex)
+                    .map_err(std::io::Error::other)
+            } else {
+                Ok(Pattern::Glob(id))
+            }
+        })
+        .collect()
+}
+
+/// Parse a game version such as "2.0.72", with omitted parts taken as zero.
+fn parse_version(s: &str) -> Result<[u16; 4], String> {
+    let mut version = [0; 4];
+    let parts: Vec<&str> = s.split('.').collect();
+    if parts.len() > version.len() {
+        return Err(format!("Expected at most 4 parts, got {}", parts.len()));
+    }
+    for (target, part) in version.iter_mut().zip(parts) {
+        *target = part
+            .parse()
+            .map_err(|err| format!("Invalid version part {part:?}: {err}"))?;
+    }
+    Ok(version)
+}
+
+/// Pretty-printed JSON that converts back to exactly the same file.
+fn to_json(data: &AchievementsDat) -> Result<String, Error> {
+    let json = serde_json::to_string_pretty(data).map_err(std::io::Error::from)?;
+    let reimported: AchievementsDat = serde_json::from_str(&json).map_err(std::io::Error::from)?;
+    if reimported.to_bytes()? != data.to_bytes()? {
+        return Err(std::io::Error::other(
+            "JSON output would not convert back to an identical file",
+        )
+        .into());
+    }
+    Ok(json)
+}
+
+/// Read the input file, or standard input if there is none.
+fn read_input(path: Option<PathBuf>) -> std::io::Result<Vec<u8>> {
+    let mut input = Vec::new();
+    match path {
+        Some(path) => File::open(path)?.read_to_end(&mut input)?,
+        None => std::io::stdin().read_to_end(&mut input)?,
+    };
+    Ok(input)
+}
+
+/// Read and parse a file given by path, printing its warnings.
+fn read_file(path: &Path) -> Result<AchievementsDat, Error> {
+    let input = std::fs::read(path)?;
+    let ParseOutcome { data, warnings } = AchievementsDat::parse(&mut Cursor::new(&input))?;
+    for warning in warnings {
+        eprintln!("Warning: {}: {warning}", path.display());
+        usage::warning(&warning);
+    }
+    Ok(data)
+}
+
+fn main() -> ExitCode {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    match matches.subcommand_name() {
+        Some("usage") => {}
+        command => usage::start(command.unwrap_or("dump")),
+    }
+    let code = match run(cli) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            // Distinct from the status 1 of a negative answer, as from has.
+            ExitCode::from(2)
+        }
+    };
+    usage::finish();
+    code
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Error> {
+    let modpack = cli
+        .modpack_meta
+        .as_deref()
+        .map(ModpackMeta::load)
+        .transpose()?;
+    let prototypes = cli
+        .prototypes
+        .as_deref()
+        .map(Prototypes::load)
+        .transpose()?;
+    let locale = (!cli.locale.is_empty())
+        .then(|| Locale::load(&cli.locale, &cli.language))
+        .transpose()?;
+    let path = match &cli.command {
+        Some(command) => command.input_path()?,
+        None => None,
+    };
+    let input_format = cli
+        .command
+        .as_ref()
+        .map_or(Format::Binary, Command::input_format);
+    #[cfg(feature = "watch")]
+    if let Some(Command::Watch { .. }) = cli.command {
+        if input_format != Format::Binary {
+            return Err(std::io::Error::other("watch can only read binary files").into());
+        }
+        let path = path.ok_or_else(|| {
+            std::io::Error::other("watch needs a file to watch, given by FILE, --file or --auto")
+        })?;
+        watch::run(&path, |data| {
+            targets(data, modpack.as_ref(), prototypes.as_ref())
+        })?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // The tui keeps the file open and locked for the whole session, instead
+    // of reading it up front like the other commands.
+    #[cfg(feature = "tui")]
+    if let Some(Command::Tui {
+        merge,
+        allow_newer,
+        force,
+        ..
+    }) = cli.command
+    {
+        if input_format != Format::Binary {
+            return Err(std::io::Error::other("tui can only edit binary files").into());
+        }
+        let path = path.ok_or_else(|| {
+            std::io::Error::other("tui needs a file to save to, given by FILE, --file or --auto")
+        })?;
+        check_running(&path, force)?;
+        warn_steam_cloud(&path);
+        let session = FileSession::open(&path)?;
+        check_version(session.edits().original(), allow_newer)?;
+        for warning in session.edits().staged().warnings() {
+            eprintln!("Warning: {warning}");
+            usage::warning(&warning);
+        }
+        tui::run(session, &path, merge, locale)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Usage {
+        enable,
+        clear,
+        disable,
+    }) = &cli.command
+    {
+        match (enable, clear, disable) {
+            (true, _, _) => usage::enable()?,
+            (_, true, _) => usage::clear()?,
+            (_, _, true) => usage::disable()?,
+            _ => usage::show()?,
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Undo { dry_run, force, .. }) = &cli.command {
+        let path = path.ok_or_else(|| {
+            std::io::Error::other("undo needs the edited file, given by FILE, --file or --auto")
+        })?;
+        let entry = journal::last(&path)?.ok_or_else(|| {
+            std::io::Error::other(format!("No edits of {} to undo", path.display()))
+        })?;
+        let mut session = FileSession::open(&path)?;
+        if !entry.left(session.edits().original()) {
+            return Err(std::io::Error::other(format!(
+                "{} was changed since the last recorded edit ({}), so it cannot be undone",
+                path.display(),
+                entry.command
+            ))
+            .into());
+        }
+        let entry = Arc::new(entry);
+        let revert = Arc::clone(&entry);
+        session
+            .edits_mut()
+            .apply(move |data| revert.revert(data))
+            .map_err(std::io::Error::other)?;
+        if *dry_run {
+            print_dry_run(session.edits().original(), session.edits().staged());
+            return Ok(ExitCode::SUCCESS);
+        }
+        check_running(&path, *force)?;
+        warn_steam_cloud(&path);
+        let before = session.edits().original().clone();
+        let after = session.edits().staged().clone();
+        let bytes = session.save()?;
+        usage::edited(session.path());
+        journal::pop(&path)?;
+        match &entry.summary {
+            Some(summary) => eprintln!("Undid {} ({})", entry.command, summary.changes()),
+            None => eprintln!("Undid {}", entry.command),
+        }
+        eprint!(
+            "{}",
+            Summary::new(&before, &after, bytes, Some(session.path().to_owned()))
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Backup {
+        backup_dir,
+        list,
+        keep,
+        older_than,
+        ..
+    }) = &cli.command
+    {
+        let dir = backup::dir(backup_dir.as_deref())?;
+        if *list {
+            let snapshots = backup::list(&dir)?;
+            for snapshot in &snapshots {
+                println!("{}", snapshot.name);
+            }
+            eprintln!("{} snapshots in {}", snapshots.len(), dir.display());
+        } else if keep.is_some() || older_than.is_some() {
+            let max_age = older_than
+                .map(backup::days)
+                .transpose()
+                .map_err(std::io::Error::other)?;
+            let pruned = backup::prune(&dir, *keep, max_age)?;
+            for snapshot in &pruned {
+                eprintln!("Deleted {}", snapshot.name);
+            }
+            eprintln!("Deleted {} snapshots from {}", pruned.len(), dir.display());
+        } else {
+            let input = read_input(path)?;
+            if let Err(err) = AchievementsDat::parse(&mut Cursor::new(&input)) {
+                eprintln!("Warning: The input does not parse, but is backed up anyway: {err}");
+            }
+            let snapshot = backup::create(&dir, &input)?;
+            eprintln!("Backed up {} bytes to {}", input.len(), snapshot.display());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Supports { version }) = &cli.command {
+        let [major, minor, patch, build] = *version;
+        let [verified_major, verified_minor] = LATEST_VERIFIED_VERSION;
+        match VersionSupport::of(*version) {
+            VersionSupport::Unsupported => {
+                return Err(std::io::Error::other(format!(
+                    "Files from Factorio {major}.{minor}.{patch}.{build} are not supported; only {MIN_SUPPORTED_MAJOR_VERSION}.0 or later"
+                ))
+                .into());
+            }
+            VersionSupport::Verified => {
+                eprintln!("Files from Factorio {major}.{minor}.{patch}.{build} are supported");
+            }
+            VersionSupport::Newer => {
+                eprintln!(
+                    "Warning: Factorio {major}.{minor}.{patch}.{build} is newer than {verified_major}.{verified_minor}, the latest version this tool was checked against; its files may contain achievement types or layouts that cannot be parsed"
+                );
+            }
+        }
+        eprintln!("Known achievement types ({}):", KNOWN_TYPES.len());
+        for typ in KNOWN_TYPES {
+            eprintln!("  {typ}");
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Validate { .. }) = &cli.command {
+        if input_format != Format::Binary {
+            return Err(std::io::Error::other("validate can only check binary files").into());
+        }
+        let input = read_input(path)?;
+        let ParseOutcome { data, warnings } = AchievementsDat::parse(&mut Cursor::new(&input))?;
+        for warning in warnings {
+            eprintln!("Warning: {warning}");
+            usage::warning(&warning);
+        }
+        let output = data.to_bytes()?;
+        let differ = input
+            .iter()
+            .zip(&output)
+            .filter(|(original, written)| original != written)
+            .count();
+        if let Some(offset) = input.iter().zip(&output).position(|(a, b)| a != b) {
+            return Err(std::io::Error::other(format!(
+                "Re-serialized file differs from the original in {differ} bytes, first at offset {offset:#x}: {:02x} became {:02x}",
+                input[offset], output[offset]
+            ))
+            .into());
+        }
+        if input.len() != output.len() {
+            return Err(std::io::Error::other(format!(
+                "Re-serialized file is {} bytes long, but the original is {} bytes",
+                output.len(),
+                input.len()
+            ))
+            .into());
+        }
+        eprintln!("All {} bytes parse and re-serialize exactly", input.len());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Discover { old, new }) = &cli.command {
+        let scan = |path: &Path| -> Result<Layout, Error> {
+            let bytes = std::fs::read(path)?;
+            // Files the parser can read tell where the tracked list starts.
+            let tracked_len = AchievementsDat::parse(&mut Cursor::new(&bytes))
+                .ok()
+                .map(|outcome| outcome.data.tracked().len());
+            Layout::scan(&bytes, tracked_len)
+                .map_err(|err| std::io::Error::other(format!("{}: {err}", path.display())).into())
+        };
+        eprint!("{}", discover::compare(&scan(old)?, &scan(new)?));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Diff { old, new }) = &cli.command {
+        let old = read_file(old)?;
+        let new = read_file(new)?;
+        let targets = targets(&new, modpack.as_ref(), prototypes.as_ref());
+        eprint!("{}", old.diff(&new).targets(targets));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let matches = Matches::default();
+    let edit = match &cli.command {
+        // The document is edited before reading the input, so that the
+        // edited file gets all the checks of other edits.
+        Some(Command::Edit { id, format, .. }) => {
+            match edit_in_editor(path.as_deref(), input_format, id.as_deref(), *format)? {
+                Some(edit) => Some(edit),
+                None => {
+                    eprintln!("The document was not changed; nothing was written");
+                    return Ok(ExitCode::SUCCESS);
+                }
+            }
+        }
+        Some(command) => command.edit(&matches)?,
+        None => None,
+    };
+    if let Some(output) = cli
+        .command
+        .as_ref()
+        .and_then(Command::output)
+        .filter(|output| output.in_place)
+    {
+        let path = path.ok_or_else(|| {
+            std::io::Error::other(
+                "--in-place needs a file to write to, given by FILE, --file or --auto",
+            )
+        })?;
+        if input_format != Format::Binary {
+            return Err(std::io::Error::other("--in-place can only edit binary files").into());
+        }
+        let edit = edit.expect("commands with an output have an edit");
+        let mut session = FileSession::open(&path)?;
+        check_version(
+            session.edits().original(),
+            output.allow_newer || output.dry_run,
+        )?;
+        for warning in session.edits().staged().warnings() {
+            eprintln!("Warning: {warning}");
+            usage::warning(&warning);
+        }
+        session
+            .edits_mut()
+            .apply(move |data| edit(data))
+            .map_err(std::io::Error::other)?;
+        if output.dry_run {
+            print_dry_run(session.edits().original(), session.edits().staged());
+            return Ok(ExitCode::SUCCESS);
+        }
+        check_running(&path, output.force)?;
+        warn_steam_cloud(&path);
+        if session.changed_on_disk()? {
+            if output.merge {
+                eprintln!(
+                    "The file was changed by another program, applying edits to the new version"
+                );
+                session.rebase()?;
+            } else {
+                return Err(std::io::Error::other(
+                    "The file was changed by another program since it was read; use --merge to apply the edits to the new version",
+                )
+                .into());
+            }
+        }
+        let before = session.edits().original().clone();
+        let after = session.edits().staged().clone();
+        let backup = output
+            .backup
+            .as_ref()
+            .map(|dir| backup::create(&backup::dir(dir.as_deref())?, session.on_disk()))
+            .transpose()?;
+        let bytes = session.save()?;
+        usage::edited(session.path());
+        let summary = Summary::new(&before, &after, bytes, Some(session.path().to_owned()))
+            .matches(&matches)
+            .backup(backup);
+        let command = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+        if let Err(err) = journal::record(&path, &command, &before, &after, &summary) {
+            eprintln!(
+                "Warning: Failed to record the edit in the journal, so it cannot be undone: {err}"
+            );
+        }
+        eprint!("{summary}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let ParseOutcome { data, warnings } = if let Some(Command::FromJson {
+        split_per_achievement: Some(dir),
+        ..
+    }) = &cli.command
+    {
+        let data = split::import(dir)?;
+        let warnings = data.warnings();
+        ParseOutcome { data, warnings }
+    } else {
+        let input = read_input(path)?;
+        if input_format == Format::Json {
+            let data: AchievementsDat =
+                serde_json::from_slice(&input).map_err(std::io::Error::from)?;
+            let warnings = data.warnings();
+            ParseOutcome { data, warnings }
+        } else {
+            // Parse from memory rather than a stream, so that binrw can rewind
+            // after failures and report the error that actually occurred.
+            AchievementsDat::parse(&mut Cursor::new(&input))?
+        }
+    };
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+        usage::warning(&warning);
+    }
+
+    match cli.command {
+        Some(Command::Dump {
+            format: Some(format),
+            types,
+            ..
+        }) => {
+            let contents: Vec<_> = data
+                .sorted_contents(None)
+                .into_iter()
+                .filter(|content| types.is_empty() || types.contains(content.typ()))
+                .collect();
+            rows::write(&contents, format)?;
+        }
+
+        None | Some(Command::Dump { .. }) => {
+            let types = match cli.command {
+                Some(Command::Dump { types, .. }) => types,
+                _ => Vec::new(),
+            };
+            eprint!("{}", data.dump().types(&types));
+            if let Some(modpack) = &modpack {
+                let described: Vec<_> = data
+                    .sorted_contents(None)
+                    .into_iter()
+                    .filter(|content| types.is_empty() || types.contains(content.typ()))
+                    .filter_map(|content| Some((content.id(), modpack.describe(content)?)))
+                    .collect();
+                let id_width = described.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+                eprintln!("Modpack: {} achievements described", described.len());
+                for (id, description) in described {
+                    eprintln!("  {id:id_width$}  {description}");
+                }
+            }
+            if let Some(prototypes) = &prototypes {
+                let goals: Vec<_> = data
+                    .sorted_contents(None)
+                    .into_iter()
+                    .filter(|content| types.is_empty() || types.contains(content.typ()))
+                    .filter_map(|content| Some((content.id(), prototypes.describe(content)?)))
+                    .collect();
+                let id_width = goals.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+                eprintln!("Goals: {} achievements", goals.len());
+                for (id, goal) in goals {
+                    eprintln!("  {id:id_width$}  {goal}");
+                }
+            }
+            if let Some(locale) = &locale {
+                let named: Vec<_> = data
+                    .sorted_contents(None)
+                    .into_iter()
+                    .filter(|content| types.is_empty() || types.contains(content.typ()))
+                    .filter_map(|content| Some((content.id(), locale.describe(content.id())?)))
+                    .collect();
+                let id_width = named.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+                eprintln!("Locale: {} achievements named", named.len());
+                for (id, text) in named {
+                    eprintln!("  {id:id_width$}  {text}");
+                }
+            }
+        }
+
+        Some(
+            Command::Delete { .. }
+            | Command::Remove { .. }
+            | Command::Add { .. }
+            | Command::Track { .. }
+            | Command::Repair { .. }
+            | Command::Untrack { .. }
+            | Command::ResetAll { .. }
+            | Command::Set { .. }
+            | Command::Edit { .. }
+            | Command::Merge { .. }
+            | Command::Restore { .. },
+        ) => {
+            let edit = edit.expect("editing commands have an edit");
+            let output = cli
+                .command
+                .as_ref()
+                .and_then(Command::output)
+                .expect("editing commands have an output");
+            check_version(&data, output.allow_newer || output.dry_run)?;
+            let edited = edit(data.clone()).map_err(std::io::Error::other)?;
+            if output.dry_run {
+                print_dry_run(&data, &edited);
+                return Ok(ExitCode::SUCCESS);
+            }
+            let bytes = match output.stdout_format {
+                Format::Binary => edited.to_bytes()?,
+                Format::Json => format!("{}\n", to_json(&edited)?).into_bytes(),
+            };
+            std::io::stdout().write_all(&bytes)?;
+            eprint!(
+                "{}",
+                Summary::new(&data, &edited, bytes.len(), None).matches(&matches)
+            );
+        }
+
+        Some(Command::FromJson { .. }) => {
+            std::io::stdout().write_all(&data.to_bytes()?)?;
+        }
+
+        Some(
+            Command::Diff { .. }
+            | Command::Discover { .. }
+            | Command::Supports { .. }
+            | Command::Validate { .. }
+            | Command::Usage { .. }
+            | Command::Backup { .. }
+            | Command::Undo { .. },
+        ) => {
+            unreachable!("handled before reading input")
+        }
+
+        #[cfg(feature = "tui")]
+        Some(Command::Tui { .. }) => unreachable!("handled before reading input"),
+
+        #[cfg(feature = "watch")]
+        Some(Command::Watch { .. }) => unreachable!("handled before reading input"),
+
+        Some(Command::ToJson {
+            split_per_achievement: Some(dir),
+            ..
+        }) => {
+            split::export(&data, &dir)?;
+        }
+
+        Some(Command::ToJson { .. }) => {
+            println!("{}", to_json(&data)?);
+        }
+
+        Some(Command::Canonicalize { .. }) => {
+            print!("{}", data.canonical());
+        }
+
+        Some(Command::Check { .. }) => {
+            let problems = data.warnings().len();
+            if problems > 0 {
+                return Err(std::io::Error::other(format!(
+                    "Found {problems} problems; the repair command can fix some of them"
+                ))
+                .into());
+            }
+            eprintln!("No problems found");
+        }
+
+        Some(Command::Tracked { .. }) => {
+            for id in data.tracked_ids() {
+                match id {
+                    Ok(id) => println!("{id}"),
+                    Err(index) => eprintln!("Warning: Tracked index {index} has no header"),
+                }
+            }
+        }
+
+        Some(Command::Header { .. }) => {
+            header::print(&data, data.to_bytes()?.len());
+        }
+
+        Some(Command::Stats { .. }) => {
+            stats::print(&data, data.to_bytes()?.len());
+        }
+
+        Some(Command::Missing { .. }) => {
+            let ids: Vec<&str> = match (&prototypes, &modpack) {
+                (Some(prototypes), _) => prototypes.ids().collect(),
+                (None, Some(modpack)) => modpack.ids().collect(),
+                (None, None) => {
+                    return Err(std::io::Error::other(
+                        "missing needs the achievements to look for from --prototypes or --modpack-meta",
+                    )
+                    .into());
+                }
+            };
+            let (mut missing, mut unstarted) = (0, 0);
+            for id in ids {
+                match data.get(id) {
+                    None => {
+                        println!("{id}");
+                        missing += 1;
+                    }
+                    Some(content) if content.is_unstarted() => {
+                        unstarted += 1;
+                    }
+                    Some(_) => {}
+                }
+            }
+            eprintln!(
+                "{missing} achievements have no entry in the file, and {unstarted} more have an entry with no progress"
+            );
+        }
+
+        Some(Command::Has { id, .. }) => {
+            if data.get(&id).is_none() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+
+        Some(Command::Eval { query, .. }) => {
+            println!("{}", query.eval(&data).map_err(std::io::Error::other)?);
+        }
+
+        Some(Command::List {
+            sort_by,
+            template,
+            progress,
+            format,
+            ids,
+            regex,
+            types,
+            ..
+        }) => {
+            let patterns = (!ids.is_empty())
+                .then(|| patterns(ids, regex))
+                .transpose()?;
+            let of_types: Option<BTreeSet<&str>> = (!types.is_empty()).then(|| {
+                types
+                    .iter()
+                    .flat_map(|typ| data.iter_by_type(typ))
+                    .map(AchievementContent::id)
+                    .collect()
+            });
+            let selected = |id: &str| {
+                patterns
+                    .as_ref()
+                    .is_none_or(|patterns| Pattern::any_matches(patterns, id))
+                    && of_types.as_ref().is_none_or(|ids| ids.contains(id))
+            };
+            // Titles from --locale and --modpack-meta, as in "  - Golem"
+            let descriptions = |content: &AchievementContent| -> String {
+                [
+                    locale
+                        .as_ref()
+                        .and_then(|locale| locale.name(content.id()))
+                        .map(str::to_string),
+                    modpack
+                        .as_ref()
+                        .and_then(|modpack| modpack.describe(content)),
+                ]
+                .into_iter()
+                .flatten()
+                .map(|description| format!("  - {description}"))
+                .collect()
+            };
+            let print_ids = |ids: Vec<&str>| {
+                let id_width = ids.iter().map(|id| id.len()).max().unwrap_or(0);
+                for id in ids {
+                    match data.get(id).map(descriptions) {
+                        Some(descriptions) if !descriptions.is_empty() => {
+                            println!("{id:id_width$}{descriptions}")
+                        }
+                        _ => println!("{id}"),
+                    }
+                }
+            };
+            match (sort_by, template) {
+                (sort_by, None) if let Some(format) = format => {
+                    let contents: Vec<_> = data
+                        .sorted_contents(sort_by.as_ref())
+                        .into_iter()
+                        .filter(|content| selected(content.id()))
+                        .collect();
+                    rows::write(&contents, format)?;
+                }
+
+                (sort_by, None) if progress => {
+                    let contents: Vec<_> = data
+                        .sorted_contents(sort_by.as_ref())
+                        .into_iter()
+                        .filter(|content| selected(content.id()))
+                        .collect();
+                    let id_width = contents.iter().map(|c| c.id().len()).max().unwrap_or(0);
+                    for content in contents {
+                        // The goal replaces the field it applies to
+                        let mut goal = prototypes
+                            .as_ref()
+                            .and_then(|prototypes| prototypes.describe(content));
+                        let fields: Vec<String> = content
+                            .progress()
+                            .fields()
+                            .into_iter()
+                            .map(|(name, value)| match value.as_f64() {
+                                Some(_) if let Some(goal) = goal.take() => goal,
+                                _ => format!("{name}: {value}"),
+                            })
+                            .collect();
+                        println!(
+                            "{:id_width$}  {}{}",
+                            content.id(),
+                            fields.join(", "),
+                            descriptions(content)
+                        );
+                    }
+                }
+
+                (sort_by, Some(template)) => {
+                    for content in data.sorted_contents(sort_by.as_ref()) {
+                        if selected(content.id()) {
+                            println!("{}", template.render(content));
+                        }
+                    }
+                }
+
+                (None, None) => {
+                    let mut ids = match &patterns {
+                        Some(patterns) => data.list_matching(patterns),
+                        None => data.list(),
+                    };
+                    ids.retain(|id| selected(id));
+                    print_ids(ids.into_iter().map(|id| id.as_ref()).collect());
+                }
+
+                (Some(key), None) => {
+                    let mut ids = data.list_sorted(&key);
+                    ids.retain(|id| selected(id));
+                    print_ids(ids.into_iter().map(|id| id.as_ref()).collect());
+                }
+            }
+        }
+    }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,176 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+
+use crate::AchievementsDat;
+use crate::FieldValue;
+use crate::entries;
+
+/// IDs of the achievements affected by [AchievementsDat::merge].
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// Achievements that were only in the other file, and were copied over.
+    pub added: Vec<String>,
+    /// Achievements that had at least one field raised to the other file's value.
+    pub updated: Vec<String>,
+    /// Achievements that could not be merged, and were left as they were:
+    /// either their types differ, or they have raw byte fields that are
+    /// nonzero and different in both files.
+    pub conflicts: Vec<String>,
+}
+
+pub(crate) fn merge(
+    data: &mut AchievementsDat,
+    other: &AchievementsDat,
+) -> Result<MergeReport, String> {
+    let mut report = MergeReport::default();
+    for theirs in other.contents.iter() {
+        let Some(ours) = data.get_mut(theirs.id()) else {
+            let their_index = entries::header_index(other, theirs.id());
+            entries::register(data, theirs.typ(), theirs.id(), their_index)?;
+            data.contents
+                .push(theirs.clone())
+                .map_err(|err| format!("{}: {err}", theirs.id()))?;
+            report.added.push(theirs.id().to_string());
+            continue;
+        };
+        if ours.typ() != theirs.typ() {
+            report.conflicts.push(theirs.id().to_string());
+            continue;
+        }
+
+        let mut raised = Vec::new();
+        let mut conflict = false;
+        for ((field, our_value), (_, their_value)) in ours
+            .progress()
+            .fields()
+            .into_iter()
+            .zip(theirs.progress().fields())
+        {
+            match (our_value, their_value) {
+                (FieldValue::Bytes(a), FieldValue::Bytes(b)) if a != b => {
+                    if a.iter().all(|byte| *byte == 0) {
+                        raised.push((field, their_value));
+                    } else if b.iter().any(|byte| *byte != 0) {
+                        conflict = true;
+                    }
+                }
+                _ => {
+                    if their_value.total_cmp(&our_value) == Ordering::Greater {
+                        raised.push((field, their_value));
+                    }
+                }
+            }
+        }
+        if conflict {
+            report.conflicts.push(theirs.id().to_string());
+            continue;
+        }
+        if !raised.is_empty() {
+            for (field, value) in raised {
+                ours.progress_mut()
+                    .set_field(field, value)
+                    .expect("fields of the same type accept each other's values");
+            }
+            report.updated.push(theirs.id().to_string());
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AchievementContent;
+    use crate::AchievementType;
+    use crate::test_data::entry;
+    use crate::test_data::file;
+
+    fn golem(max_damage: f64, survived: u8) -> AchievementContent {
+        entry(
+            AchievementType::PlayerDamaged,
+            "golem",
+            &[
+                ("max_damage", FieldValue::F64(max_damage)),
+                ("survived", FieldValue::U8(survived)),
+            ],
+        )
+    }
+
+    fn lazy_bastard(bytes: &[u8]) -> AchievementContent {
+        entry(
+            AchievementType::DontCraftManually,
+            "lazy-bastard",
+            &[("0", FieldValue::Bytes(bytes))],
+        )
+    }
+
+    #[test]
+    fn keeps_the_higher_value_of_each_field() {
+        let mut ours = file([golem(200.0, 0)]);
+        let theirs = file([golem(150.5, 1)]);
+        let report = merge(&mut ours, &theirs).unwrap();
+        assert_eq!(report.updated, ["golem"]);
+        assert_eq!(ours.get("golem"), Some(&golem(200.0, 1)));
+    }
+
+    #[test]
+    fn lower_values_leave_the_achievement_unchanged() {
+        let mut ours = file([golem(200.0, 1)]);
+        let report = merge(&mut ours, &file([golem(150.5, 0)])).unwrap();
+        assert!(report.updated.is_empty());
+        assert_eq!(ours.get("golem"), Some(&golem(200.0, 1)));
+    }
+
+    #[test]
+    fn copies_achievements_only_in_the_other_file() {
+        let mut ours = file([golem(200.0, 1)]);
+        let theirs = file([lazy_bastard(&[3, 0, 0, 0])]);
+        let report = merge(&mut ours, &theirs).unwrap();
+        assert_eq!(report.added, ["lazy-bastard"]);
+        assert_eq!(ours.get("lazy-bastard"), theirs.get("lazy-bastard"));
+        assert!(ours.warnings().is_empty(), "{:?}", ours.warnings());
+    }
+
+    #[test]
+    fn different_types_conflict() {
+        let mut ours = file([golem(200.0, 1)]);
+        let theirs = file([entry(
+            AchievementType::Kill,
+            "golem",
+            &[("max_killed", FieldValue::U32(7))],
+        )]);
+        let report = merge(&mut ours, &theirs).unwrap();
+        assert_eq!(report.conflicts, ["golem"]);
+        assert_eq!(ours.get("golem"), Some(&golem(200.0, 1)));
+    }
+
+    #[test]
+    fn raw_bytes_are_taken_only_over_zeroes() {
+        let mut ours = file([lazy_bastard(&[0, 0, 0, 0])]);
+        let report = merge(&mut ours, &file([lazy_bastard(&[3, 0, 0, 0])])).unwrap();
+        assert_eq!(report.updated, ["lazy-bastard"]);
+        assert_eq!(ours.get("lazy-bastard"), Some(&lazy_bastard(&[3, 0, 0, 0])));
+
+        let report = merge(&mut ours, &file([lazy_bastard(&[0, 0, 0, 0])])).unwrap();
+        assert!(report.updated.is_empty() && report.conflicts.is_empty());
+
+        let report = merge(&mut ours, &file([lazy_bastard(&[1, 0, 0, 0])])).unwrap();
+        assert_eq!(report.conflicts, ["lazy-bastard"]);
+        assert_eq!(ours.get("lazy-bastard"), Some(&lazy_bastard(&[3, 0, 0, 0])));
+    }
+}
@@ -0,0 +1,115 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Human labels, categories and targets for achievement IDs, loaded from a
+//! TOML file such as one shipped by a modpack whose achievement IDs say
+//! little on their own:
+//!
+//! ```toml
+//! [achievements.se-probe-1]
+//! label = "Into the unknown"
+//! category = "Exploration"
+//! target = 10
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use factorio_achievements_editor::AchievementContent;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModpackMeta {
+    #[serde(default)]
+    achievements: BTreeMap<String, AchievementMeta>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AchievementMeta {
+    label: Option<String>,
+    category: Option<String>,
+    /// The value of [Self::field] at which the achievement is completed.
+    target: Option<f64>,
+    /// The progress field [Self::target] applies to. By default the first
+    /// numeric field.
+    field: Option<String>,
+}
+
+impl ModpackMeta {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        toml::from_str(&std::fs::read_to_string(path)?).map_err(|err| {
+            std::io::Error::new(ErrorKind::InvalidData, format!("{}: {err}", path.display()))
+        })
+    }
+
+    /// IDs of all achievements the file has anything on, in order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.achievements.keys().map(String::as_str)
+    }
+
+    /// The progress field of `content` that its target applies to, and the
+    /// target, if the file has one for it.
+    pub fn target(&self, content: &AchievementContent) -> Option<(&'static str, f64)> {
+        let meta = self.achievements.get(content.id())?;
+        let target = meta.target?;
+        let (name, _) = meta.field(content)?;
+        Some((name, target))
+    }
+
+    /// The label, category and progress towards the target of `content`, as
+    /// in "Into the unknown (Exploration, 30% of 10 max_launched)", if the
+    /// file has anything on it.
+    pub fn describe(&self, content: &AchievementContent) -> Option<String> {
+        let meta = self.achievements.get(content.id())?;
+        let mut details = Vec::new();
+        if let Some(category) = &meta.category {
+            details.push(category.clone());
+        }
+        if let Some(target) = meta.target {
+            match meta.field(content) {
+                Some((name, value)) if target != 0.0 => {
+                    details.push(format!("{:.0}% of {target} {name}", 100.0 * value / target))
+                }
+                _ => details.push(format!("target {target}")),
+            }
+        }
+        match (&meta.label, details.is_empty()) {
+            (Some(label), true) => Some(label.clone()),
+            (Some(label), false) => Some(format!("{label} ({})", details.join(", "))),
+            (None, false) => Some(details.join(", ")),
+            (None, true) => None,
+        }
+    }
+}
+
+impl AchievementMeta {
+    /// The name and value of the progress field of `content` that
+    /// [Self::target] applies to.
+    fn field(&self, content: &AchievementContent) -> Option<(&'static str, f64)> {
+        content
+            .progress()
+            .fields()
+            .into_iter()
+            .find(|(name, value)| match &self.field {
+                Some(field) => name == field,
+                None => value.as_f64().is_some(),
+            })
+            .and_then(|(name, value)| Some((name, value.as_f64()?)))
+    }
+}
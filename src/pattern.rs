@@ -0,0 +1,150 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+/// A pattern selecting achievements by ID.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// Matches exactly this ID.
+    Exact(String),
+    /// Matches the whole ID, where `*` matches any sequence of characters and
+    /// `?` matches any single character.
+    Glob(String),
+    /// Matches IDs that contain a match for the regular expression anywhere.
+    /// Use `^` and `$` to match the whole ID.
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn matches(&self, id: &str) -> bool {
+        match self {
+            Self::Exact(exact) => id == exact,
+            Self::Glob(glob) => glob_matches(glob, id),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.is_match(id),
+        }
+    }
+
+    /// Whether any of `patterns` matches `id`.
+    pub fn any_matches(patterns: &[Self], id: &str) -> bool {
+        patterns.iter().any(|pattern| pattern.matches(id))
+    }
+}
+
+fn glob_matches(glob: &str, id: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let id: Vec<char> = id.chars().collect();
+    let (mut g, mut i) = (0, 0);
+    // Position of the last `*` seen, and of the input it was last retried at.
+    let mut backtrack = None;
+    while i < id.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, i));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == id[i] => {
+                g += 1;
+                i += 1;
+            }
+            _ => match backtrack {
+                Some((star, retry)) => {
+                    g = star + 1;
+                    i = retry + 1;
+                    backtrack = Some((star, retry + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(glob: &str, id: &str) -> bool {
+        Pattern::Glob(glob.to_string()).matches(id)
+    }
+
+    #[test]
+    fn star_at_start_middle_and_end() {
+        assert!(glob("*-1", "mass-production-1"));
+        assert!(!glob("*-1", "mass-production-2"));
+        assert!(glob("mass-*-1", "mass-production-1"));
+        assert!(glob("mass-*-1", "mass--1"));
+        assert!(!glob("mass-*-1", "mass-production-2"));
+        assert!(glob("mass-*", "mass-production-1"));
+        assert!(glob("mass-*", "mass-"));
+        assert!(!glob("mass-*", "mass"));
+        assert!(glob("*", "golem"));
+        assert!(glob("*", ""));
+        assert!(glob("**", "golem"));
+    }
+
+    #[test]
+    fn star_backtracks() {
+        assert!(glob("*a*b", "aaab"));
+        assert!(glob("*-*-1", "mass-production-1"));
+        assert!(!glob("*-*-1", "golem-1"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_character() {
+        assert!(glob("mass-production-?", "mass-production-1"));
+        assert!(!glob("mass-production-?", "mass-production-"));
+        assert!(!glob("mass-production-?", "mass-production-10"));
+        assert!(glob("g?lem", "gölem"));
+    }
+
+    #[test]
+    fn empty_pattern_matches_only_empty_id() {
+        assert!(glob("", ""));
+        assert!(!glob("", "golem"));
+    }
+
+    #[test]
+    fn literal_pattern_matches_whole_id() {
+        assert!(glob("golem", "golem"));
+        assert!(!glob("golem", "golems"));
+        assert!(!glob("golem", "the-golem"));
+        assert!(Pattern::Exact("golem".to_string()).matches("golem"));
+        assert!(!Pattern::Exact("gol*".to_string()).matches("golem"));
+    }
+
+    #[test]
+    fn no_match() {
+        let patterns = [
+            Pattern::Glob("produce-*".to_string()),
+            Pattern::Exact("golem".to_string()),
+        ];
+        assert!(!Pattern::any_matches(&patterns, "steamrolled"));
+        assert!(Pattern::any_matches(&patterns, "golem"));
+        assert!(!Pattern::any_matches(&[], "golem"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_matches_anywhere() {
+        let pattern = Pattern::Regex(Regex::new("duct").unwrap());
+        assert!(pattern.matches("mass-production-1"));
+        assert!(!Pattern::Regex(Regex::new("^duct").unwrap()).matches("mass-production-1"));
+    }
+}
@@ -0,0 +1,122 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Goal values of achievements, read from the prototype data that
+//! `factorio --dump-data` writes to `script-output/data-raw-dump.json`:
+//!
+//! ```json
+//! {"produce-achievement": {"mass-production-1": {"amount": 10000, ...}}}
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use factorio_achievements_editor::AchievementContent;
+use serde_json::Value;
+
+/// Prototype properties that hold an achievement's goal, in order of
+/// preference.
+const GOAL_KEYS: &[&str] = &[
+    "amount",
+    "count",
+    "limit",
+    "minimum_distance",
+    "minimum_damage",
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct Prototypes {
+    /// Goal values by achievement ID, for every achievement prototype.
+    goals: BTreeMap<String, Option<f64>>,
+}
+
+impl Prototypes {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let invalid = |err: String| {
+            std::io::Error::new(ErrorKind::InvalidData, format!("{}: {err}", path.display()))
+        };
+        let raw: BTreeMap<String, Value> = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|err| invalid(err.to_string()))?;
+        let goals = raw
+            .iter()
+            .filter(|(typ, _)| typ.ends_with("achievement"))
+            .filter_map(|(_, prototypes)| prototypes.as_object())
+            .flatten()
+            .map(|(id, prototype)| {
+                let goal = GOAL_KEYS
+                    .iter()
+                    .find_map(|key| prototype.get(key)?.as_f64());
+                (id.clone(), goal)
+            })
+            .collect();
+        Ok(Self { goals })
+    }
+
+    /// The goal value of the achievement with ID `id`, if its prototype has
+    /// one.
+    pub fn goal(&self, id: &str) -> Option<f64> {
+        self.goals.get(id).copied().flatten()
+    }
+
+    /// IDs of all achievement prototypes, in order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.goals.keys().map(String::as_str)
+    }
+
+    /// The first numeric field of `content` and its goal, if its prototype
+    /// has one.
+    pub fn target(&self, content: &AchievementContent) -> Option<(&'static str, f64)> {
+        let goal = self.goal(content.id())?;
+        let (name, _) = content
+            .progress()
+            .fields()
+            .into_iter()
+            .find(|(_, value)| value.as_f64().is_some())?;
+        Some((name, goal))
+    }
+
+    /// The first numeric field of `content` against its goal, as in
+    /// "produced 3.2M / 20M", if its prototype has a goal.
+    pub fn describe(&self, content: &AchievementContent) -> Option<String> {
+        let goal = self.goal(content.id())?;
+        let (name, value) = content
+            .progress()
+            .fields()
+            .into_iter()
+            .find_map(|(name, value)| Some((name, value.as_f64()?)))?;
+        Some(format!(
+            "{name} {} / {}",
+            abbreviate(value),
+            abbreviate(goal)
+        ))
+    }
+}
+
+/// `value` with a k, M or G suffix and at most one decimal if it is a
+/// thousand or more.
+fn abbreviate(value: f64) -> String {
+    let (scaled, suffix) = [(1e9, "G"), (1e6, "M"), (1e3, "k")]
+        .into_iter()
+        .find(|(scale, _)| value.abs() >= *scale)
+        .map_or((value, ""), |(scale, suffix)| (value / scale, suffix));
+    if suffix.is_empty() {
+        format!("{scaled}")
+    } else {
+        let rounded = format!("{scaled:.1}");
+        format!("{}{suffix}", rounded.trim_end_matches(".0"))
+    }
+}
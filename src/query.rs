@@ -0,0 +1,290 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use crate::AchievementContent;
+use crate::AchievementsDat;
+use crate::Pattern;
+use crate::fields::check_path;
+
+/// A query over the achievements in a file, evaluating to a single value,
+/// parsed from strings like `count`, `exists where id=golem` or
+/// `sum(progress.produced) where type=produce-achievement and id!=*-1`.
+///
+/// Conditions after `where` compare the text of an [AchievementContent::field]
+/// path with a glob pattern; achievements without the field never match `=`
+/// and always match `!=`.
+#[derive(Clone, Debug)]
+pub struct Query {
+    aggregate: Aggregate,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Aggregate {
+    Count,
+    Exists,
+    Sum(String),
+    Min(String),
+    Max(String),
+}
+
+#[derive(Clone, Debug)]
+struct Condition {
+    path: String,
+    negated: bool,
+    pattern: Pattern,
+}
+
+/// The result of evaluating a [Query].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueryValue {
+    Count(usize),
+    Bool(bool),
+    Number(f64),
+}
+
+impl Display for QueryValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Count(v) => v.fmt(f),
+            Self::Bool(v) => v.fmt(f),
+            Self::Number(v) => v.fmt(f),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, content: &AchievementContent) -> bool {
+        let matches = content
+            .field(&self.path)
+            .is_some_and(|value| self.pattern.matches(&value.to_string()));
+        matches != self.negated
+    }
+}
+
+impl Query {
+    /// Fails if `min` or `max` selects no achievements with a numeric value
+    /// for the field, since there is then no value to return.
+    pub fn eval(&self, data: &AchievementsDat) -> Result<QueryValue, String> {
+        let selected: Vec<&AchievementContent> = data
+            .contents()
+            .iter()
+            .filter(|content| self.conditions.iter().all(|cond| cond.matches(content)))
+            .collect();
+        let numbers = |path: &str| -> Vec<f64> {
+            selected
+                .iter()
+                .filter_map(|content| content.field(path).and_then(|value| value.as_f64()))
+                .collect()
+        };
+        let extreme = |path: &str, pick: fn(f64, f64) -> f64| {
+            numbers(path)
+                .into_iter()
+                .reduce(pick)
+                .map(QueryValue::Number)
+                .ok_or_else(|| format!("No selected achievement has a numeric {path}"))
+        };
+        match &self.aggregate {
+            Aggregate::Count => Ok(QueryValue::Count(selected.len())),
+            Aggregate::Exists => Ok(QueryValue::Bool(!selected.is_empty())),
+            Aggregate::Sum(path) => Ok(QueryValue::Number(numbers(path).into_iter().sum())),
+            Aggregate::Min(path) => extreme(path, f64::min),
+            Aggregate::Max(path) => extreme(path, f64::max),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (head, conditions) = match s.split_once(" where ") {
+            Some((head, conditions)) => (head.trim(), Some(conditions)),
+            None => (s.trim(), None),
+        };
+
+        let (name, arg) = match head.split_once('(') {
+            Some((name, rest)) => {
+                let arg = rest
+                    .strip_suffix(')')
+                    .ok_or_else(|| format!("Expected ) at the end of: {head}"))?
+                    .trim();
+                (name.trim(), (!arg.is_empty()).then_some(arg))
+            }
+            None => (head, None),
+        };
+        let path = |arg: Option<&str>| -> Result<String, String> {
+            let path =
+                arg.ok_or_else(|| format!("{name} needs a field, e.g. {name}(progress.produced)"))?;
+            check_path(path)?;
+            Ok(path.to_string())
+        };
+        let aggregate = match (name, arg) {
+            ("count", None) => Aggregate::Count,
+            ("exists", None) => Aggregate::Exists,
+            ("count" | "exists", Some(arg)) => {
+                return Err(format!("{name} takes no field, got: {arg}"));
+            }
+            ("sum", arg) => Aggregate::Sum(path(arg)?),
+            ("min", arg) => Aggregate::Min(path(arg)?),
+            ("max", arg) => Aggregate::Max(path(arg)?),
+            (other, _) => {
+                return Err(format!(
+                    "Expected count, exists, sum, min or max, got: {other}"
+                ));
+            }
+        };
+
+        let conditions = match conditions {
+            None => Vec::new(),
+            Some(conditions) => conditions
+                .split(" and ")
+                .map(|cond| {
+                    let (path, negated, pattern) = match cond.split_once("!=") {
+                        Some((path, pattern)) => (path, true, pattern),
+                        None => {
+                            let (path, pattern) = cond
+                                .split_once('=')
+                                .ok_or_else(|| format!("Expected PATH=PATTERN, got: {cond}"))?;
+                            (path, false, pattern)
+                        }
+                    };
+                    let path = path.trim().to_string();
+                    check_path(&path)?;
+                    Ok(Condition {
+                        path,
+                        negated,
+                        pattern: Pattern::Glob(pattern.trim().to_string()),
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        };
+
+        Ok(Self {
+            aggregate,
+            conditions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AchievementType;
+    use crate::FieldValue;
+    use crate::test_data::entry;
+    use crate::test_data::file;
+
+    fn eval(query: &str) -> Result<QueryValue, String> {
+        let data = file([
+            entry(
+                AchievementType::Produce,
+                "mass-production-1",
+                &[("produced", FieldValue::F64(3200.0))],
+            ),
+            entry(
+                AchievementType::Produce,
+                "mass-production-2",
+                &[("produced", FieldValue::F64(800.0))],
+            ),
+            entry(
+                AchievementType::Kill,
+                "steamrolled",
+                &[("max_killed", FieldValue::U32(7))],
+            ),
+        ]);
+        query.parse::<Query>()?.eval(&data)
+    }
+
+    #[test]
+    fn parses_aggregates() {
+        let aggregate = |query: &str| query.parse::<Query>().unwrap().aggregate;
+        assert_eq!(aggregate("count"), Aggregate::Count);
+        assert_eq!(aggregate(" exists "), Aggregate::Exists);
+        assert_eq!(
+            aggregate("sum(progress.produced)"),
+            Aggregate::Sum("progress.produced".to_string())
+        );
+        assert_eq!(
+            aggregate("min( progress.produced )"),
+            Aggregate::Min("progress.produced".to_string())
+        );
+        assert_eq!(aggregate("max(id)"), Aggregate::Max("id".to_string()));
+    }
+
+    #[test]
+    fn parses_conditions() {
+        let query: Query = "count where type=produce-* and id != *-1".parse().unwrap();
+        let conditions: Vec<(&str, bool)> = query
+            .conditions
+            .iter()
+            .map(|cond| (cond.path.as_str(), cond.negated))
+            .collect();
+        assert_eq!(conditions, [("type", false), ("id", true)]);
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        let err = |query: &str| query.parse::<Query>().unwrap_err();
+        assert_eq!(
+            err("average(progress.produced)"),
+            "Expected count, exists, sum, min or max, got: average"
+        );
+        assert_eq!(err("count(id)"), "count takes no field, got: id");
+        assert_eq!(err("sum"), "sum needs a field, e.g. sum(progress.produced)");
+        assert_eq!(
+            err("sum()"),
+            "sum needs a field, e.g. sum(progress.produced)"
+        );
+        assert_eq!(
+            err("max(progress.produced"),
+            "Expected ) at the end of: max(progress.produced"
+        );
+        assert_eq!(err("count where id"), "Expected PATH=PATTERN, got: id");
+        assert!(err("sum(produced)").starts_with("Expected id, type or progress."));
+        assert!(err("count where produced=1").starts_with("Expected id, type or progress."));
+    }
+
+    #[test]
+    fn evaluates_over_selected_achievements() {
+        assert_eq!(eval("count"), Ok(QueryValue::Count(3)));
+        assert_eq!(
+            eval("count where type=produce-achievement"),
+            Ok(QueryValue::Count(2))
+        );
+        assert_eq!(eval("exists where id=golem"), Ok(QueryValue::Bool(false)));
+        assert_eq!(
+            eval("sum(progress.produced)"),
+            Ok(QueryValue::Number(4000.0))
+        );
+        assert_eq!(
+            eval("max(progress.produced) where id!=*-1"),
+            Ok(QueryValue::Number(800.0))
+        );
+        assert_eq!(
+            eval("count where progress.produced!=*"),
+            Ok(QueryValue::Count(1))
+        );
+        assert_eq!(
+            eval("min(progress.produced) where type=kill-achievement"),
+            Err("No selected achievement has a numeric progress.produced".to_string())
+        );
+    }
+}
@@ -0,0 +1,217 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use crate::AchievementType;
+use crate::AchievementsDat;
+use crate::Warning;
+use crate::entries;
+use crate::warnings;
+
+/// Fix the headers and tracked list of `data` so that they agree with each
+/// other and with the contents, and return the warnings that this resolved.
+pub(crate) fn repair(data: &mut AchievementsDat) -> Result<Vec<Warning>, String> {
+    let before = warnings::collect(data);
+
+    // Of several registrations of the same ID, keep the first one whose type
+    // matches a content entry, or else the first one.
+    let content_keys: BTreeSet<(&AchievementType, &str)> = data
+        .contents
+        .iter()
+        .map(|content| (&content.typ, content.id.as_ref()))
+        .collect();
+    let mut keep: BTreeMap<String, (usize, usize, bool)> = BTreeMap::new();
+    for (h, header) in data.headers.iter().enumerate() {
+        for (s, sub) in header.subobjects.iter().enumerate() {
+            let matches = content_keys.contains(&(&header.typ, sub.id.as_ref()));
+            match keep.get(sub.id.as_ref()) {
+                Some((_, _, true)) => {}
+                Some((_, _, false)) if !matches => {}
+                _ => {
+                    keep.insert(sub.id.to_string(), (h, s, matches));
+                }
+            }
+        }
+    }
+    for (h, header) in data.headers.iter_mut().enumerate() {
+        let mut s = 0;
+        header.subobjects.retain(|sub| {
+            let kept = keep
+                .get(sub.id.as_ref())
+                .is_some_and(|(kh, ks, _)| (*kh, *ks) == (h, s));
+            s += 1;
+            kept
+        });
+    }
+    data.headers.retain(|header| !header.subobjects.is_empty());
+
+    // Give later registrations of a shared index a new one.
+    let mut next = data
+        .headers
+        .iter()
+        .flat_map(|header| header.subobjects.iter())
+        .map(|sub| sub.index.saturating_add(1))
+        .max()
+        .unwrap_or(0);
+    let mut indices = BTreeSet::new();
+    for header in data.headers.iter_mut() {
+        for sub in header.subobjects.iter_mut() {
+            if !indices.insert(sub.index) {
+                if indices.contains(&next) {
+                    return Err("No free header index left".to_string());
+                }
+                sub.index = next;
+                indices.insert(next);
+                next = next.saturating_add(1);
+            }
+        }
+    }
+
+    let unregistered: Vec<(String, String)> = before
+        .iter()
+        .filter_map(|warning| match warning {
+            Warning::UnregisteredContent { typ, id } => Some((typ.clone(), id.clone())),
+            _ => None,
+        })
+        .collect();
+    for (typ, id) in unregistered {
+        if entries::header_index(data, &id).is_none() {
+            entries::register(data, &AchievementType::from(typ.as_str()), &id, None)?;
+        }
+    }
+
+    let mut tracked = BTreeSet::new();
+    data.tracked
+        .retain(|index| indices.contains(index) && tracked.insert(*index));
+
+    let after = warnings::collect(data);
+    Ok(before
+        .into_iter()
+        .filter(|warning| !after.contains(warning))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AchievementContent;
+    use crate::FieldValue;
+    use crate::HeaderSubobject;
+    use crate::test_data::entry;
+    use crate::test_data::file;
+
+    fn steamrolled() -> AchievementContent {
+        entry(
+            AchievementType::Kill,
+            "steamrolled",
+            &[("max_killed", FieldValue::U32(7))],
+        )
+    }
+
+    fn golem() -> AchievementContent {
+        entry(
+            AchievementType::PlayerDamaged,
+            "golem",
+            &[("max_damage", FieldValue::F64(200.0))],
+        )
+    }
+
+    fn index(data: &AchievementsDat, id: &str) -> u16 {
+        entries::header_index(data, id).unwrap()
+    }
+
+    #[test]
+    fn sound_files_are_left_alone() {
+        let mut data = file([steamrolled(), golem()]);
+        data.tracked = vec![index(&data, "golem")];
+        let before = data.clone();
+        assert_eq!(repair(&mut data).unwrap(), []);
+        assert_eq!(data, before);
+    }
+
+    #[test]
+    fn keeps_the_registration_matching_the_contents() {
+        let mut data = file([steamrolled(), golem()]);
+        let golem_index = index(&data, "golem");
+        data.headers.as_mut_slice()[0]
+            .subobjects
+            .push(HeaderSubobject {
+                id: "golem".into(),
+                index: 9,
+            })
+            .unwrap();
+
+        let resolved = repair(&mut data).unwrap();
+        assert_eq!(
+            resolved,
+            [Warning::DuplicateRegistration {
+                id: "golem".to_string()
+            }]
+        );
+        assert_eq!(data.warnings(), []);
+        assert_eq!(data.headers[0].subobjects().len(), 1);
+        assert_eq!(data.headers[1].subobjects()[0].index(), golem_index);
+    }
+
+    #[test]
+    fn renumbers_shared_indices() {
+        let mut data = file([steamrolled(), golem()]);
+        let shared = index(&data, "steamrolled");
+        data.headers.as_mut_slice()[1].subobjects.as_mut_slice()[0].index = shared;
+
+        let resolved = repair(&mut data).unwrap();
+        assert_eq!(resolved, [Warning::DuplicateIndex { index: shared }]);
+        assert_eq!(data.warnings(), []);
+        assert_eq!(index(&data, "steamrolled"), shared);
+        assert_ne!(index(&data, "golem"), shared);
+    }
+
+    #[test]
+    fn registers_unregistered_contents() {
+        let mut data = file([steamrolled(), golem()]);
+        data.headers.remove(1);
+
+        let resolved = repair(&mut data).unwrap();
+        assert_eq!(
+            resolved,
+            [Warning::UnregisteredContent {
+                typ: "player-damaged-achievement".to_string(),
+                id: "golem".to_string()
+            }]
+        );
+        assert_eq!(data.warnings(), []);
+        assert_ne!(index(&data, "golem"), index(&data, "steamrolled"));
+    }
+
+    #[test]
+    fn drops_dangling_and_repeated_tracked_indices() {
+        let mut data = file([steamrolled(), golem()]);
+        let golem_index = index(&data, "golem");
+        data.tracked = vec![golem_index, 99, golem_index];
+
+        let resolved = repair(&mut data).unwrap();
+        assert_eq!(
+            resolved,
+            [
+                Warning::DanglingTracked { index: 99 },
+                Warning::DuplicateTracked { index: golem_index }
+            ]
+        );
+        assert_eq!(data.tracked, [golem_index]);
+    }
+}
@@ -0,0 +1,84 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! One row per achievement with its type, ID and progress fields, for
+//! spreadsheets and other tools.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use factorio_achievements_editor::AchievementContent;
+
+/// Formats for list --format and dump --format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum RowFormat {
+    /// Comma-separated values, with a header row
+    Csv,
+    /// Tab-separated values, with a header row
+    Tsv,
+    /// One JSON object per line, as in the contents of to-json
+    Ndjson,
+}
+
+/// Write `contents` to standard output in `format`.
+pub fn write(contents: &[&AchievementContent], format: RowFormat) -> std::io::Result<()> {
+    match format {
+        RowFormat::Csv => write_table(contents, b','),
+        RowFormat::Tsv => write_table(contents, b'\t'),
+        RowFormat::Ndjson => {
+            let mut stdout = std::io::stdout().lock();
+            for content in contents {
+                serde_json::to_writer(&mut stdout, content)?;
+                stdout.write_all(b"\n")?;
+            }
+            stdout.flush()
+        }
+    }
+}
+
+/// Delimiter-separated values, with a column for every progress field any
+/// achievement has. Fields an achievement doesn't have are left empty.
+fn write_table(contents: &[&AchievementContent], delimiter: u8) -> std::io::Result<()> {
+    let mut columns: Vec<&str> = Vec::new();
+    for content in contents {
+        for (name, _) in content.progress().fields() {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(std::io::stdout().lock());
+    writer.write_record(["type", "id"].iter().chain(&columns))?;
+    for content in contents {
+        let fields = content.progress().fields();
+        let progress = columns.iter().map(|column| {
+            fields
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_default()
+        });
+        writer.write_record(
+            [content.typ().to_string(), content.id().to_string()]
+                .into_iter()
+                .chain(progress),
+        )?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,85 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Detection of a running game, which writes its achievements file when it
+//! exits and so would silently undo any edit made to it in the meantime.
+
+use std::fs::File;
+use std::fs::TryLockError;
+use std::path::Path;
+
+use factorio_achievements_editor::locate;
+
+/// Why Factorio seems to be running, if `path` is one of the game's
+/// achievements files and it does.
+pub fn factorio_running(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    if !locate::FILE_NAMES.contains(&name) {
+        return None;
+    }
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    // The game holds a lock on this file in its data directory while running
+    let lock = dir.join(".lock");
+    if let Ok(file) = File::open(&lock)
+        && let Err(TryLockError::WouldBlock) = file.try_lock()
+    {
+        return Some(format!("{} is locked", lock.display()));
+    }
+    process()
+}
+
+#[cfg(target_os = "linux")]
+fn process() -> Option<String> {
+    std::fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .find_map(|entry| {
+            let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            (comm.trim() == "factorio")
+                .then(|| format!("process {}", entry.file_name().to_string_lossy()))
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn process() -> Option<String> {
+    let output = std::process::Command::new("pgrep")
+        .args(["-x", "factorio"])
+        .output()
+        .ok()?;
+    let pid = String::from_utf8_lossy(&output.stdout);
+    let pid = pid.lines().next()?;
+    Some(format!("process {pid}"))
+}
+
+#[cfg(target_os = "windows")]
+fn process() -> Option<String> {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", "IMAGENAME eq factorio.exe", "/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .to_lowercase()
+        .contains("factorio.exe")
+        .then(|| "process factorio.exe".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn process() -> Option<String> {
+    None
+}
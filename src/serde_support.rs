@@ -0,0 +1,126 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serializer;
+
+use crate::AchievementContent;
+use crate::AchievementProgress;
+use crate::AchievementType;
+use crate::FieldValue;
+use crate::fields::non_finite_f32;
+use crate::fields::non_finite_f64;
+
+/// Progress is serialized untagged, so it can only be decoded once the
+/// achievement type is known.
+#[derive(Deserialize)]
+pub(crate) struct RawContent {
+    #[serde(rename = "type")]
+    typ: AchievementType,
+    id: String,
+    progress: RawProgress,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawProgress {
+    None,
+    Single(RawValue),
+    Fields(BTreeMap<String, RawValue>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawValue {
+    Number(f64),
+    Bytes(Vec<u8>),
+    /// Non-finite numbers.
+    Text(String),
+}
+
+impl RawValue {
+    fn as_field_value(&self) -> FieldValue<'_> {
+        match self {
+            Self::Number(v) => FieldValue::F64(*v),
+            Self::Bytes(v) => FieldValue::Bytes(v),
+            Self::Text(v) => FieldValue::Str(v),
+        }
+    }
+}
+
+impl TryFrom<RawContent> for AchievementContent {
+    type Error = String;
+
+    fn try_from(raw: RawContent) -> Result<Self, Self::Error> {
+        let mut progress = AchievementProgress::default_for(&raw.typ)
+            .ok_or_else(|| format!("Unknown achievement type: {}", raw.typ))?;
+        let expected: Vec<&str> = progress.fields().iter().map(|(name, _)| *name).collect();
+        let context = |err| format!("Invalid progress for {}: {err}", raw.id);
+
+        let given: Vec<(String, FieldValue)> = match &raw.progress {
+            RawProgress::None => vec![],
+            RawProgress::Single(v) => vec![("0".to_string(), v.as_field_value())],
+            RawProgress::Fields(fields) => fields
+                .iter()
+                .map(|(name, v)| (name.clone(), v.as_field_value()))
+                .collect(),
+        };
+        let mut given_names: Vec<&str> = given.iter().map(|(name, _)| name.as_str()).collect();
+        given_names.sort();
+        let mut expected_names = expected.clone();
+        expected_names.sort();
+        if given_names != expected_names {
+            return Err(context(format!(
+                "expected fields [{}], got [{}]",
+                expected.join(", "),
+                given_names.join(", ")
+            )));
+        }
+
+        for (name, value) in given {
+            progress.set_field(&name, value).map_err(context)?;
+        }
+
+        Ok(Self {
+            typ: raw.typ,
+            id: raw.id.into(),
+            progress,
+        })
+    }
+}
+
+/// Serialize an `f32` as the `f64` with the same value. Every `f32` is exactly
+/// representable as an `f64`, so this survives being parsed back as `f64` and
+/// narrowed to `f32`, which the shortest `f32` representation does not in
+/// rare double rounding cases. Non-finite values are serialized as text, as
+/// given by [crate::fields::non_finite_f32].
+pub(crate) fn serialize_f32<S: Serializer>(v: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    match non_finite_f32(*v) {
+        Some(text) => serializer.serialize_str(&text),
+        None => serializer.serialize_f64((*v).into()),
+    }
+}
+
+/// Serialize an `f64` as a number if finite, otherwise as text as given by
+/// [crate::fields::non_finite_f64], since JSON has no numbers for those.
+pub(crate) fn serialize_f64<S: Serializer>(v: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    match non_finite_f64(*v) {
+        Some(text) => serializer.serialize_str(&text),
+        None => serializer.serialize_f64(*v),
+    }
+}
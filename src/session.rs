@@ -0,0 +1,134 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use crate::AchievementsDat;
+use crate::Warning;
+
+/// An edit that can be applied to an [EditSession], and replayed by
+/// [EditSession::rebase].
+pub type Edit = Arc<dyn Fn(AchievementsDat) -> Result<AchievementsDat, String> + Send + Sync>;
+
+/// Staged edits to an [AchievementsDat]. Each edit is applied to a copy and
+/// only kept if it succeeds, and [Self::commit] checks the result before
+/// anything is written, so callers never write a partially edited or invalid
+/// file.
+#[derive(Clone)]
+pub struct EditSession {
+    original: AchievementsDat,
+    original_warnings: Vec<Warning>,
+    staged: AchievementsDat,
+    edits: Vec<Edit>,
+}
+
+impl EditSession {
+    pub fn new(data: AchievementsDat) -> Self {
+        Self {
+            original_warnings: data.warnings(),
+            staged: data.clone(),
+            original: data,
+            edits: Vec::new(),
+        }
+    }
+
+    /// The data with all edits so far applied.
+    pub fn staged(&self) -> &AchievementsDat {
+        &self.staged
+    }
+
+    /// The data as of the last commit, or as given to [Self::new].
+    pub fn original(&self) -> &AchievementsDat {
+        &self.original
+    }
+
+    /// Whether any edits have been applied since the last commit or rollback.
+    pub fn is_modified(&self) -> bool {
+        !self.edits.is_empty()
+    }
+
+    /// Apply `edit` to a copy of the staged data. If it fails, the staged
+    /// data is left as it was.
+    pub fn apply(
+        &mut self,
+        edit: impl Fn(AchievementsDat) -> Result<AchievementsDat, String> + Send + Sync + 'static,
+    ) -> Result<(), String> {
+        self.staged = edit(self.staged.clone())?;
+        self.edits.push(Arc::new(edit));
+        Ok(())
+    }
+
+    /// Discard all edits since the last commit.
+    pub fn rollback(&mut self) {
+        self.staged = self.original.clone();
+        self.edits.clear();
+    }
+
+    /// Replace the original with `data`, for example a newer version of the
+    /// file, and replay all edits since the last commit onto it. If any edit
+    /// fails, nothing is changed.
+    pub fn rebase(&mut self, data: AchievementsDat) -> Result<(), String> {
+        self.staged = self
+            .edits
+            .iter()
+            .try_fold(data.clone(), |staged, edit| edit(staged))?;
+        self.original_warnings = data.warnings();
+        self.original = data;
+        Ok(())
+    }
+
+    /// Warnings about the staged data that the original data did not have.
+    pub fn new_warnings(&self) -> Vec<Warning> {
+        self.staged
+            .warnings()
+            .into_iter()
+            .filter(|warning| !self.original_warnings.contains(warning))
+            .collect()
+    }
+
+    /// Serialize the staged data for writing, and make it the new original.
+    /// Fails without changing anything if the edits introduced new warnings
+    /// or the data cannot be serialized.
+    #[cfg(feature = "write")]
+    pub fn commit(&mut self) -> Result<Vec<u8>, String> {
+        let warnings = self.new_warnings();
+        if !warnings.is_empty() {
+            return Err(warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "));
+        }
+        let bytes = self.staged.to_bytes().map_err(|err| err.to_string())?;
+        self.original = self.staged.clone();
+        self.original_warnings = self.original.warnings();
+        self.edits.clear();
+        Ok(bytes)
+    }
+}
+
+impl Debug for EditSession {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("EditSession")
+            .field("original", &self.original)
+            .field("original_warnings", &self.original_warnings)
+            .field("staged", &self.staged)
+            .field("edits", &self.edits.len())
+            .finish()
+    }
+}
@@ -0,0 +1,239 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Export to and import from a directory with one JSON file per
+//! achievement, grouped by type, so that changes to individual achievements
+//! show up as changes to individual files under version control.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use factorio_achievements_editor::AchievementsDat;
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+/// Everything but the progress entries, in the format of to-json.
+const META_FILE: &str = "achievements.json";
+
+/// Key in [META_FILE] listing the `TYPE/ID.json` files written with it, so
+/// that later exports remove only files they wrote themselves.
+const FILES_KEY: &str = "files";
+
+/// Write `data` to `dir` as [META_FILE] and one `TYPE/ID.json` file per
+/// achievement. Files written by the previous export for achievements no
+/// longer in `data` are removed. Refuses to write into a non-empty directory
+/// without a [META_FILE], which is unlikely to be an earlier export.
+pub fn export(data: &AchievementsDat, dir: &Path) -> std::io::Result<()> {
+    let meta_path = dir.join(META_FILE);
+    let previous = if meta_path.is_file() {
+        listed_files(dir, &read_json(&meta_path)?)?
+    } else if dir
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        return Err(std::io::Error::other(format!(
+            "{} is not empty and has no {META_FILE}, so it was not written by --split-per-achievement",
+            dir.display()
+        )));
+    } else {
+        Vec::new()
+    };
+
+    let mut meta = serde_json::to_value(data)?;
+    let contents = match meta
+        .as_object_mut()
+        .and_then(|meta| meta.remove("contents"))
+    {
+        Some(Value::Array(contents)) => contents,
+        _ => unreachable!("AchievementsDat serializes to an object with a contents array"),
+    };
+
+    fs::create_dir_all(dir)?;
+    let mut written = BTreeSet::new();
+    let mut files = Vec::new();
+    for (content, achievement) in contents.iter().zip(data.contents()) {
+        let path = entry_path(dir, achievement.typ().as_str(), achievement.id())?;
+        if !written.insert(path.clone()) {
+            return Err(std::io::Error::other(format!(
+                "Achievement {} occurs more than once, which cannot be represented as files",
+                achievement.id()
+            )));
+        }
+        fs::create_dir_all(path.parent().expect("entry paths are inside dir"))?;
+        write_json(&path, content)?;
+        files.push(Value::String(format!(
+            "{}/{}.json",
+            achievement.typ(),
+            achievement.id()
+        )));
+    }
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(FILES_KEY.to_string(), Value::Array(files));
+    }
+    write_json(&meta_path, &meta)?;
+
+    for path in previous {
+        if !written.contains(&path) && path.is_file() {
+            fs::remove_file(&path)?;
+            eprintln!("Removed {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// The files listed under [FILES_KEY] in `meta`, read from `dir`.
+fn listed_files(dir: &Path, meta: &Value) -> std::io::Result<Vec<PathBuf>> {
+    let Some(files) = meta.get(FILES_KEY).and_then(Value::as_array) else {
+        return Ok(Vec::new());
+    };
+    files
+        .iter()
+        .map(|file| {
+            let (typ, id) = file
+                .as_str()
+                .and_then(|file| file.strip_suffix(".json"))
+                .and_then(|file| file.split_once('/'))
+                .ok_or_else(|| {
+                    std::io::Error::other(format!(
+                        "{}: expected \"TYPE/ID.json\" in {FILES_KEY}, got {file}",
+                        dir.join(META_FILE).display()
+                    ))
+                })?;
+            entry_path(dir, typ, id)
+        })
+        .collect()
+}
+
+/// Read a directory written by [export]. Achievements are read in the order
+/// listed in [META_FILE], so that the file reads back exactly as exported,
+/// followed by any files not listed there in order of type and ID. They need
+/// not all be present; those registered in a header but missing a file are
+/// warned about.
+pub fn import(dir: &Path) -> std::io::Result<AchievementsDat> {
+    let mut meta = read_json(&dir.join(META_FILE))?;
+    let mut files = entry_files(dir)?;
+    let listed = listed_files(dir, &meta)?;
+    files.sort_by_key(|path| {
+        listed
+            .iter()
+            .position(|listed| listed == path)
+            .unwrap_or(listed.len())
+    });
+    let mut contents = Vec::new();
+    for path in files {
+        let content = read_json(&path)?;
+        let typ = path.parent().and_then(Path::file_name);
+        let id = path.file_stem();
+        if content.get("type").and_then(Value::as_str) != typ.and_then(|typ| typ.to_str())
+            || content.get("id").and_then(Value::as_str) != id.and_then(|id| id.to_str())
+        {
+            return Err(std::io::Error::other(format!(
+                "{}: type and id do not match the file's path",
+                path.display()
+            )));
+        }
+        contents.push(content);
+    }
+    match meta.as_object_mut() {
+        Some(meta) => {
+            meta.remove(FILES_KEY);
+            meta.insert("contents".to_string(), Value::Array(contents))
+        }
+        None => {
+            return Err(std::io::Error::other(format!(
+                "{}: expected a JSON object",
+                dir.join(META_FILE).display()
+            )));
+        }
+    };
+    let data: AchievementsDat = serde_json::from_value(meta)?;
+
+    for header in data.headers() {
+        for sub in header.subobjects() {
+            if data.get(sub.id()).is_none() {
+                eprintln!(
+                    "Warning: Achievement {} ({}) is registered in a header but has no file in {}",
+                    sub.id(),
+                    header.typ(),
+                    dir.display()
+                );
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// `dir/TYPE/ID.json`, refusing names that would escape the directory.
+fn entry_path(dir: &Path, typ: &str, id: &str) -> std::io::Result<PathBuf> {
+    for name in [typ, id] {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\', ':']) {
+            return Err(std::io::Error::other(format!(
+                "Cannot use {name:?} as a file name"
+            )));
+        }
+    }
+    Ok(dir.join(typ).join(format!("{id}.json")))
+}
+
+/// The `TYPE/ID.json` files currently in `dir`, sorted by path.
+fn entry_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for typ_dir in fs::read_dir(dir)? {
+        let typ_dir = typ_dir?;
+        if !typ_dir.file_type()?.is_dir() || typ_dir.file_name().to_string_lossy().starts_with('.')
+        {
+            continue;
+        }
+        for entry in fs::read_dir(typ_dir.path())? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") && path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_json(path: &Path) -> std::io::Result<Value> {
+    let json = fs::read(path)
+        .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+    serde_json::from_slice(&json)
+        .map_err(|err| std::io::Error::other(format!("{}: {err}", path.display())))
+}
+
+/// Write to a new file and rename it over `path`, so that a crash midway
+/// leaves either the old or the new contents.
+fn write_json(path: &Path, value: &Value) -> std::io::Result<()> {
+    let mut json = serde_json::to_string_pretty(value)?;
+    json.push('\n');
+    let write = || {
+        let mut new = NamedTempFile::new_in(path.parent().expect("entry paths are inside dir"))?;
+        if let Ok(metadata) = fs::metadata(path) {
+            new.as_file().set_permissions(metadata.permissions())?;
+        }
+        new.write_all(json.as_bytes())?;
+        new.as_file().sync_all()?;
+        new.persist(path).map_err(|err| err.error)?;
+        Ok(())
+    };
+    write().map_err(|err: std::io::Error| {
+        std::io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+    })
+}
@@ -0,0 +1,76 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Aggregate counts and section sizes, as a quick sanity check before and
+//! after edits.
+
+use std::collections::BTreeMap;
+
+use factorio_achievements_editor::AchievementType;
+use factorio_achievements_editor::AchievementsDat;
+
+use crate::header;
+use crate::header::Sections;
+
+/// Print the statistics of `data`, read from a file of `len` bytes, to
+/// standard error.
+pub fn print(data: &AchievementsDat, len: usize) {
+    let [major, minor, patch, build] = data.version();
+    // Achievements and those of them with progress, by type
+    let mut types: BTreeMap<&AchievementType, (usize, usize)> = BTreeMap::new();
+    for content in data.contents() {
+        let (count, started) = types.entry(content.typ()).or_default();
+        *count += 1;
+        if !content.is_unstarted() {
+            *started += 1;
+        }
+    }
+    let started: usize = types.values().map(|(_, started)| started).sum();
+    let sections = Sections::of(data, len);
+
+    eprintln!(
+        "Version:       {major}.{minor}.{patch}.{build} ({})",
+        header::support_name(data)
+    );
+    eprintln!(
+        "Achievements:  {} ({started} with progress, {} without)",
+        data.contents().len(),
+        data.contents().len() - started
+    );
+    eprintln!("Tracked:       {}", data.tracked().len());
+    eprintln!("Types:         {}", types.len());
+    let typ_width = types
+        .keys()
+        .map(|typ| typ.as_str().len())
+        .max()
+        .unwrap_or(0);
+    for (typ, (count, started)) in &types {
+        eprintln!(
+            "  {:typ_width$}  {count} ({started} with progress)",
+            typ.as_str()
+        );
+    }
+    eprintln!("Sections:");
+    for (name, range) in [
+        ("Preamble", sections.preamble),
+        ("Headers", sections.headers),
+        ("Progress", sections.progress),
+        ("Tracked", sections.tracked),
+    ] {
+        eprintln!("  {name:8}  {} bytes", range.len());
+    }
+    eprintln!("  {:8}  {len} bytes", "Total");
+}
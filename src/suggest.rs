@@ -0,0 +1,81 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::AchievementsDat;
+
+/// At most this many suggestions are made for a mistyped ID.
+const MAX_SUGGESTIONS: usize = 3;
+
+pub(crate) fn similar_ids<'a>(data: &'a AchievementsDat, id: &str) -> Vec<&'a str> {
+    let max_distance = (id.chars().count() / 3).max(2);
+    let mut candidates: Vec<(usize, &str)> = data
+        .contents
+        .iter()
+        .map(|content| content.id.as_ref())
+        .filter(|candidate| *candidate != id)
+        .map(|candidate| (edit_distance(id, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+pub(crate) fn resolve_prefix<'a>(
+    data: &'a AchievementsDat,
+    prefix: &str,
+) -> Result<&'a str, String> {
+    if let Some(content) = data.get(prefix) {
+        return Ok(content.id.as_ref());
+    }
+    let mut matches: Vec<&str> = data
+        .contents
+        .iter()
+        .map(|content| content.id.as_ref())
+        .filter(|id| id.starts_with(prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    match matches.as_slice() {
+        [id] => Ok(id),
+        [] => Err(format!("No achievement ID starts with {prefix}")),
+        _ => Err(format!(
+            "{prefix} is ambiguous, it could be any of {}",
+            matches.join(", ")
+        )),
+    }
+}
+
+/// Levenshtein distance: the number of single-character insertions,
+/// deletions and substitutions that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
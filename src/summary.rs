@@ -0,0 +1,137 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use factorio_achievements_editor::AchievementsDat;
+use factorio_achievements_editor::Change;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// What an editing command did, printed to standard error when it is done
+/// and kept in the journal for edits made in place.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Summary {
+    examined: usize,
+    added: usize,
+    removed: usize,
+    modified: usize,
+    /// Achievements the command selected, for commands that select them.
+    #[serde(default)]
+    matched: Option<usize>,
+    /// Selected achievements the command could not apply to, such as those
+    /// without the field set assigns.
+    #[serde(default)]
+    skipped: Option<usize>,
+    bytes: usize,
+    /// [None] for standard output.
+    output: Option<PathBuf>,
+    /// Snapshot of the file taken before it was written, with --backup.
+    #[serde(default)]
+    backup: Option<PathBuf>,
+}
+
+/// The matched and skipped counts of an edit, set by the edit each time it
+/// is applied so that a replayed edit reports its last run.
+#[derive(Clone, Debug, Default)]
+pub struct Matches(Arc<Mutex<Option<(usize, usize)>>>);
+
+impl Matches {
+    pub fn set(&self, matched: usize, skipped: usize) {
+        *self.0.lock().expect("not poisoned") = Some((matched, skipped));
+    }
+}
+
+impl Summary {
+    pub fn new(
+        before: &AchievementsDat,
+        after: &AchievementsDat,
+        bytes: usize,
+        output: Option<PathBuf>,
+    ) -> Self {
+        let diff = before.diff(after);
+        let count = |keep: fn(&Change) -> bool| diff.changes.iter().filter(|c| keep(c)).count();
+        let modified: BTreeSet<&str> = diff
+            .changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::TypeChanged { id, .. } | Change::FieldChanged { id, .. } => Some(*id),
+                Change::Added(_) | Change::Removed(_) => None,
+            })
+            .collect();
+        Self {
+            examined: before.contents().len(),
+            added: count(|change| matches!(change, Change::Added(_))),
+            removed: count(|change| matches!(change, Change::Removed(_))),
+            modified: modified.len(),
+            matched: None,
+            skipped: None,
+            bytes,
+            output,
+            backup: None,
+        }
+    }
+
+    /// Include the counts last set in `matches`, if any.
+    pub fn matches(self, matches: &Matches) -> Self {
+        match *matches.0.lock().expect("not poisoned") {
+            Some((matched, skipped)) => Self {
+                matched: Some(matched),
+                skipped: Some(skipped),
+                ..self
+            },
+            None => self,
+        }
+    }
+
+    pub fn backup(self, backup: Option<PathBuf>) -> Self {
+        Self { backup, ..self }
+    }
+
+    /// The changed counts alone, as in "1 modified, 0 removed, 0 added".
+    pub fn changes(&self) -> String {
+        format!(
+            "{} modified, {} removed, {} added",
+            self.modified, self.removed, self.added
+        )
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unchanged = self.examined - self.modified - self.removed;
+        write!(f, "Examined {} achievements: ", self.examined)?;
+        if let (Some(matched), Some(skipped)) = (self.matched, self.skipped) {
+            write!(f, "{matched} matched, {skipped} skipped, ")?;
+        }
+        writeln!(
+            f,
+            "{} modified, {unchanged} unchanged, {} removed, {} added",
+            self.modified, self.removed, self.added
+        )?;
+        if let Some(backup) = &self.backup {
+            writeln!(f, "Backed up the file to {}", backup.display())?;
+        }
+        match &self.output {
+            Some(path) => writeln!(f, "Wrote {} bytes to {}", self.bytes, path.display()),
+            None => writeln!(f, "Wrote {} bytes to standard output", self.bytes),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::AchievementContent;
+use crate::fields::check_path;
+
+/// A line template such as `{id}\t{type}\t{progress.produced}`, where each
+/// `{...}` is a path for [AchievementContent::field]. Fields missing from an
+/// entry render as an empty string. `\t`, `\n` and `\\` are unescaped, and
+/// `{{` and `}}` produce literal braces.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(String),
+}
+
+impl Template {
+    pub fn render(&self, content: &AchievementContent) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Field(path) => {
+                    if let Some(value) = content.field(path) {
+                        write!(out, "{value}").expect("Writing to a String cannot fail");
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut path = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => path.push(c),
+                            None => return Err("Unclosed { in template".into()),
+                        }
+                    }
+                    check_path(&path)?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(path));
+                }
+                '}' => return Err("Unmatched } in template; use }} for a literal brace".into()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AchievementType;
+    use crate::FieldValue;
+    use crate::test_data::entry;
+
+    fn render(template: &str) -> String {
+        let content = entry(
+            AchievementType::Produce,
+            "mass-production-1",
+            &[("produced", FieldValue::F64(3200.0))],
+        );
+        template.parse::<Template>().unwrap().render(&content)
+    }
+
+    #[test]
+    fn renders_fields_and_escapes() {
+        assert_eq!(
+            render(r"{id}\t{type}\t{progress.produced}\n"),
+            "mass-production-1\tproduce-achievement\t3200\n"
+        );
+        assert_eq!(render(r"a\\b \x"), r"a\b \x");
+        assert_eq!(render("{{id}} = {id}"), "{id} = mass-production-1");
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn missing_fields_render_empty() {
+        assert_eq!(render("[{progress.max_killed}]"), "[]");
+    }
+
+    #[test]
+    fn invalid_templates_are_errors() {
+        assert_eq!(
+            "{id".parse::<Template>(),
+            Err("Unclosed { in template".to_string())
+        );
+        assert!(
+            "id}"
+                .parse::<Template>()
+                .unwrap_err()
+                .starts_with("Unmatched }")
+        );
+        assert!("{produced}".parse::<Template>().is_err());
+    }
+}
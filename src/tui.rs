@@ -0,0 +1,302 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Interactive browser and editor for the terminal.
+
+use std::path::Path;
+
+use factorio_achievements_editor::Assignment;
+use factorio_achievements_editor::FileSession;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::crossterm::event;
+use ratatui::crossterm::event::Event;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEventKind;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::widgets::Block;
+use ratatui::widgets::List;
+use ratatui::widgets::ListState;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Wrap;
+
+use crate::locale::Locale;
+
+const HELP: &str =
+    "↑/↓ select  e edit  r reset  u undo all  q save and quit  Esc quit without saving";
+
+enum Mode {
+    Browse,
+    Edit(String),
+    ConfirmDiscard,
+    ConfirmMerge,
+}
+
+struct App<'a> {
+    session: FileSession,
+    path: &'a Path,
+    ids: Vec<String>,
+    selected: ListState,
+    mode: Mode,
+    status: String,
+    merge: bool,
+    locale: Option<Locale>,
+}
+
+/// Browse and edit the file until the user quits, and save it if the user
+/// chose to. `path` is only used for display. If `merge` is set, edits are
+/// applied to the file's new contents without asking if another program
+/// changed it in the meantime. Titles and descriptions from `locale` are
+/// shown with the progress of the selected achievement.
+pub fn run(
+    session: FileSession,
+    path: &Path,
+    merge: bool,
+    locale: Option<Locale>,
+) -> std::io::Result<()> {
+    let ids = session
+        .edits()
+        .staged()
+        .sorted_contents(None)
+        .into_iter()
+        .map(|content| content.id().to_string())
+        .collect();
+    let mut app = App {
+        session,
+        path,
+        ids,
+        selected: ListState::default().with_selected(Some(0)),
+        mode: Mode::Browse,
+        status: HELP.to_string(),
+        merge,
+        locale,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match &mut self.mode {
+                Mode::Browse => match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => self.selected.select_previous(),
+                    KeyCode::Down | KeyCode::Char('j') => self.selected.select_next(),
+                    KeyCode::PageUp => self.selected.scroll_up_by(10),
+                    KeyCode::PageDown => self.selected.scroll_down_by(10),
+                    KeyCode::Home => self.selected.select_first(),
+                    KeyCode::End => self.selected.select_last(),
+                    KeyCode::Char('e') if self.selected_id().is_some() => {
+                        self.mode = Mode::Edit(String::new());
+                    }
+                    KeyCode::Char('r') => self.reset(),
+                    KeyCode::Char('u') => {
+                        self.session.edits_mut().rollback();
+                        self.status = "Undid all changes".to_string();
+                    }
+                    KeyCode::Char('q') if !self.session.edits().is_modified() => return Ok(()),
+                    KeyCode::Char('q') => match self.session.changed_on_disk() {
+                        Ok(true) if !self.merge => {
+                            self.mode = Mode::ConfirmMerge;
+                            self.status = "The file was changed by another program. Press m to apply your edits to the new version and save, any other key to go back".to_string();
+                        }
+                        Ok(_) => match self.merge_and_save() {
+                            Ok(()) => return Ok(()),
+                            Err(err) => self.status = format!("Not saved: {err}"),
+                        },
+                        Err(err) => self.status = format!("Not saved: {err}"),
+                    },
+                    KeyCode::Esc if self.session.edits().is_modified() => {
+                        self.mode = Mode::ConfirmDiscard;
+                        self.status = "Discard unsaved changes? Press Esc again to quit, any other key to go back".to_string();
+                    }
+                    KeyCode::Esc => return Ok(()),
+                    _ => {}
+                },
+
+                Mode::Edit(input) => match key.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Enter => {
+                        let input = std::mem::take(input);
+                        self.mode = Mode::Browse;
+                        self.edit(&input);
+                    }
+                    KeyCode::Esc => {
+                        self.mode = Mode::Browse;
+                        self.status = HELP.to_string();
+                    }
+                    _ => {}
+                },
+
+                Mode::ConfirmDiscard => match key.code {
+                    KeyCode::Esc => return Ok(()),
+                    _ => {
+                        self.mode = Mode::Browse;
+                        self.status = HELP.to_string();
+                    }
+                },
+
+                Mode::ConfirmMerge => {
+                    self.mode = Mode::Browse;
+                    self.status = HELP.to_string();
+                    if key.code == KeyCode::Char('m') {
+                        match self.merge_and_save() {
+                            Ok(()) => return Ok(()),
+                            Err(err) => self.status = format!("Not saved: {err}"),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn selected_id(&self) -> Option<&str> {
+        self.selected
+            .selected()
+            .and_then(|i| self.ids.get(i))
+            .map(String::as_str)
+    }
+
+    fn reset(&mut self) {
+        if let Some(id) = self.selected_id().map(str::to_string) {
+            let edit_id = id.clone();
+            self.status = match self.session.edits_mut().apply(move |mut data| {
+                data.delete(&edit_id);
+                Ok(data)
+            }) {
+                Ok(()) => format!("Reset {id}"),
+                Err(err) => err,
+            };
+        }
+    }
+
+    fn edit(&mut self, input: &str) {
+        let Some(id) = self.selected_id().map(str::to_string) else {
+            return;
+        };
+        let edit_id = id.clone();
+        let result = input.parse::<Assignment>().and_then(|assignment| {
+            self.session.edits_mut().apply(move |mut data| {
+                let id = &edit_id;
+                let report = data.set_matching(|content| content.id() == id, &assignment)?;
+                if report.modified.is_empty() {
+                    Err(format!("{id} has no field named {}", assignment.field))
+                } else {
+                    Ok(data)
+                }
+            })
+        });
+        self.status = match result {
+            Ok(()) => format!("Set {input} on {id}"),
+            Err(err) => err,
+        };
+    }
+
+    /// Save, first replaying the edits onto the file's current contents if
+    /// another program changed it.
+    fn merge_and_save(&mut self) -> std::io::Result<()> {
+        if self.session.changed_on_disk()? {
+            self.session
+                .rebase()
+                .map_err(|err| std::io::Error::other(err.to_string()))?;
+        }
+        self.session.save().map(|_| ())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+
+        let title = if self.session.edits().is_modified() {
+            format!("{} (modified)", self.path.display())
+        } else {
+            self.path.display().to_string()
+        };
+        frame.render_stateful_widget(
+            List::new(self.ids.iter().map(String::as_str))
+                .block(Block::bordered().title(title))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.selected,
+        );
+
+        let content = self.selected_id().and_then(|id| {
+            self.session
+                .edits()
+                .staged()
+                .sorted_contents(None)
+                .into_iter()
+                .find(|content| content.id() == id)
+        });
+        let text = match content {
+            Some(content) => [
+                self.locale
+                    .as_ref()
+                    .and_then(|locale| locale.name(content.id()))
+                    .map(|name| format!("name: {name}")),
+                self.locale
+                    .as_ref()
+                    .and_then(|locale| locale.description(content.id()))
+                    .map(|description| format!("description: {description}")),
+                Some(format!("type: {}", content.typ())),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(
+                content
+                    .progress()
+                    .fields()
+                    .into_iter()
+                    .map(|(name, value)| format!("{name}: {value}")),
+            )
+            .collect::<Vec<_>>()
+            .join("\n"),
+            None => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Progress")),
+            details,
+        );
+
+        let status_line = match &self.mode {
+            Mode::Edit(input) => format!("FIELD=VALUE: {input}"),
+            Mode::Browse | Mode::ConfirmDiscard | Mode::ConfirmMerge => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+}
@@ -0,0 +1,170 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Opt-in usage statistics, kept only in a local file for the user's own
+//! interest and never sent anywhere: how often each command was run, which
+//! files were edited and which warnings were seen. Nothing is recorded
+//! unless the file exists, which `usage --enable` creates.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use factorio_achievements_editor::locate;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The statistics of this run, if enabled, to be saved by [finish].
+static STATS: Mutex<Option<Stats>> = Mutex::new(None);
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Stats {
+    commands: BTreeMap<String, u64>,
+    files_edited: BTreeMap<String, u64>,
+    warnings: BTreeMap<String, u64>,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (title, counts) in [
+            ("Commands run", &self.commands),
+            ("Files edited", &self.files_edited),
+            ("Warnings seen", &self.warnings),
+        ] {
+            writeln!(f, "{title}:")?;
+            let mut counts: Vec<_> = counts.iter().collect();
+            counts.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+            for (key, count) in counts {
+                writeln!(f, "  {count:6}  {key}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn path() -> std::io::Result<PathBuf> {
+    locate::config_dir()
+        .map(|dir| dir.join("usage.json"))
+        .ok_or_else(|| {
+            std::io::Error::other("Found no configuration directory to keep usage statistics in")
+        })
+}
+
+fn load(path: &Path) -> std::io::Result<Stats> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+fn save(path: &Path, stats: &Stats) -> std::io::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(stats)? + "\n")
+}
+
+/// Start recording this run of `command`, if statistics are enabled.
+pub fn start(command: &str) {
+    let Ok(path) = path() else { return };
+    if !path.is_file() {
+        return;
+    }
+    match load(&path) {
+        Ok(mut stats) => {
+            *stats.commands.entry(command.to_string()).or_default() += 1;
+            *STATS.lock().expect("no panics while holding the lock") = Some(stats);
+        }
+        Err(err) => eprintln!("Warning: Failed to read {}: {err}", path.display()),
+    }
+}
+
+pub fn edited(file: &Path) {
+    if let Some(stats) = STATS
+        .lock()
+        .expect("no panics while holding the lock")
+        .as_mut()
+    {
+        *stats
+            .files_edited
+            .entry(file.display().to_string())
+            .or_default() += 1;
+    }
+}
+
+pub fn warning(warning: &dyn Display) {
+    if let Some(stats) = STATS
+        .lock()
+        .expect("no panics while holding the lock")
+        .as_mut()
+    {
+        *stats.warnings.entry(warning.to_string()).or_default() += 1;
+    }
+}
+
+/// Save what was recorded since [start], if anything.
+pub fn finish() {
+    let Some(stats) = STATS
+        .lock()
+        .expect("no panics while holding the lock")
+        .take()
+    else {
+        return;
+    };
+    if let Err(err) = path().and_then(|path| save(&path, &stats)) {
+        eprintln!("Warning: Failed to save usage statistics: {err}");
+    }
+}
+
+/// Print the statistics to standard error.
+pub fn show() -> std::io::Result<()> {
+    let path = path()?;
+    if !path.is_file() {
+        eprintln!("Usage statistics are not enabled; enable them with usage --enable");
+        return Ok(());
+    }
+    eprintln!("Usage statistics in {}:", path.display());
+    eprint!("{}", load(&path)?);
+    Ok(())
+}
+
+/// Start keeping statistics, unless already keeping them.
+pub fn enable() -> std::io::Result<()> {
+    let path = path()?;
+    if !path.is_file() {
+        std::fs::create_dir_all(path.parent().expect("the file is in the config dir"))?;
+        save(&path, &Stats::default())?;
+    }
+    eprintln!("Keeping usage statistics in {}", path.display());
+    Ok(())
+}
+
+/// Forget the statistics kept so far, but keep recording new ones.
+pub fn clear() -> std::io::Result<()> {
+    let path = path()?;
+    if path.is_file() {
+        save(&path, &Stats::default())?;
+    }
+    eprintln!("Usage statistics cleared");
+    Ok(())
+}
+
+/// Stop keeping statistics, and delete those kept so far.
+pub fn disable() -> std::io::Result<()> {
+    let path = path()?;
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    eprintln!("Usage statistics are disabled and deleted");
+    Ok(())
+}
@@ -0,0 +1,183 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::AchievementsDat;
+
+/// The result of [AchievementsDat::parse]: the parsed file and anything
+/// suspicious noticed about it, which did not prevent parsing.
+#[derive(Debug)]
+pub struct ParseOutcome {
+    pub data: AchievementsDat,
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+    /// A header registers a type this parser has no decoder for. Parsing only
+    /// succeeds as long as no content entry has that type.
+    UnknownHeaderType { typ: String },
+    /// A content entry whose type and ID are not registered in any header.
+    UnregisteredContent { typ: String, id: String },
+    /// More than one content entry with the same type and ID.
+    DuplicateContent { typ: String, id: String },
+    /// An ID registered more than once in the headers.
+    DuplicateRegistration { id: String },
+    /// A header index shared by more than one registered ID, which makes
+    /// tracking ambiguous.
+    DuplicateIndex { index: u16 },
+    /// A tracked index that no header subobject has.
+    DanglingTracked { index: u16 },
+    /// An index tracked more than once.
+    DuplicateTracked { index: u16 },
+    /// A progress field that is infinite or NaN, which Factorio is unlikely
+    /// to have written on purpose.
+    NonFiniteProgress {
+        typ: String,
+        id: String,
+        field: String,
+        value: String,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::UnknownHeaderType { typ } => {
+                write!(f, "Unknown achievement type in header: {typ}")
+            }
+            Self::UnregisteredContent { typ, id } => {
+                write!(
+                    f,
+                    "Achievement {id} ({typ}) is not registered in any header"
+                )
+            }
+            Self::DuplicateContent { typ, id } => {
+                write!(f, "Achievement {id} ({typ}) appears more than once")
+            }
+            Self::DuplicateRegistration { id } => {
+                write!(
+                    f,
+                    "Achievement {id} is registered more than once in the headers"
+                )
+            }
+            Self::DuplicateIndex { index } => {
+                write!(
+                    f,
+                    "Header index {index} is used by more than one achievement"
+                )
+            }
+            Self::DanglingTracked { index } => {
+                write!(f, "Tracked index {index} does not match any header entry")
+            }
+            Self::DuplicateTracked { index } => {
+                write!(f, "Tracked index {index} appears more than once")
+            }
+            Self::NonFiniteProgress {
+                typ,
+                id,
+                field,
+                value,
+            } => {
+                write!(
+                    f,
+                    "Achievement {id} ({typ}) has non-finite progress {field} = {value}"
+                )
+            }
+        }
+    }
+}
+
+pub(crate) fn collect(data: &AchievementsDat) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for header in data.headers.iter() {
+        if !header.typ.is_known() {
+            warnings.push(Warning::UnknownHeaderType {
+                typ: header.typ.to_string(),
+            });
+        }
+    }
+
+    let registered: BTreeSet<(&str, &str)> = data
+        .headers
+        .iter()
+        .flat_map(|header| {
+            header
+                .subobjects
+                .iter()
+                .map(|sub| (header.typ.as_str(), sub.id.as_ref()))
+        })
+        .collect();
+    let mut ids = BTreeSet::new();
+    let mut indices = BTreeSet::new();
+    for sub in data
+        .headers
+        .iter()
+        .flat_map(|header| header.subobjects.iter())
+    {
+        if !ids.insert(sub.id.as_ref()) {
+            warnings.push(Warning::DuplicateRegistration {
+                id: sub.id.to_string(),
+            });
+        }
+        if !indices.insert(sub.index) {
+            warnings.push(Warning::DuplicateIndex { index: sub.index });
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    for content in data.contents.iter() {
+        let key = (content.typ.as_str(), content.id.as_ref());
+        if !registered.contains(&key) {
+            warnings.push(Warning::UnregisteredContent {
+                typ: key.0.to_string(),
+                id: key.1.to_string(),
+            });
+        }
+        if !seen.insert(key) {
+            warnings.push(Warning::DuplicateContent {
+                typ: key.0.to_string(),
+                id: key.1.to_string(),
+            });
+        }
+        for (field, value) in content.progress.fields() {
+            if value.as_f64().is_some_and(|v| !v.is_finite()) {
+                warnings.push(Warning::NonFiniteProgress {
+                    typ: key.0.to_string(),
+                    id: key.1.to_string(),
+                    field: field.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut tracked = BTreeSet::new();
+    for index in &data.tracked {
+        if !indices.contains(index) {
+            warnings.push(Warning::DanglingTracked { index: *index });
+        }
+        if !tracked.insert(*index) {
+            warnings.push(Warning::DuplicateTracked { index: *index });
+        }
+    }
+
+    warnings
+}
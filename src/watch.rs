@@ -0,0 +1,136 @@
+// Factorio achievements editor
+// Copyright (C) 2025  Emil Lundberg
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Live diff of the progress changes the game writes to a file during play.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use factorio_achievements_editor::AchievementsDat;
+use notify::EventKind;
+use notify::RecursiveMode;
+use notify::Watcher;
+
+/// How long to wait for more events after one arrives, since the game may
+/// write the file in several steps.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// How long to wait before each new attempt to read the file when it fails,
+/// since the game may still be writing it.
+const RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(400),
+    Duration::from_millis(800),
+];
+
+/// Print the changes to the file at `path` to standard error each time it
+/// is written, until interrupted, with progress towards the `targets` of
+/// each version of the file.
+pub fn run(
+    path: &Path,
+    targets: impl Fn(&AchievementsDat) -> BTreeMap<(&str, &'static str), f64>,
+) -> std::io::Result<()> {
+    let mut previous = read_with_retries(path)?;
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(std::io::Error::other)?;
+    // Watch the directory rather than the file, since the file may be
+    // replaced rather than written to
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(std::io::Error::other)?;
+    eprintln!(
+        "Watching {} for changes, press Ctrl+C to stop",
+        path.display()
+    );
+
+    let name = path.file_name();
+    for event in &receiver {
+        let event = event.map_err(std::io::Error::other)?;
+        let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event
+                .paths
+                .iter()
+                .any(|changed| changed.file_name() == name);
+        if !relevant {
+            continue;
+        }
+        std::thread::sleep(SETTLE);
+        while receiver.try_recv().is_ok() {}
+
+        match read_with_retries(path) {
+            Ok(current) => {
+                let diff = previous
+                    .diff(&current)
+                    .targets(targets(&current))
+                    .to_string();
+                if !diff.is_empty() {
+                    eprint!("[{}]\n{diff}", time_of_day());
+                }
+                previous = current;
+            }
+            Err(err) => eprintln!("Warning: Failed to read {}: {err}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// [read], retried after each of [RETRY_DELAYS] until it succeeds.
+fn read_with_retries(path: &Path) -> std::io::Result<AchievementsDat> {
+    let mut delays = RETRY_DELAYS.into_iter();
+    loop {
+        match read(path) {
+            Ok(data) => return Ok(data),
+            Err(err) => match delays.next() {
+                Some(delay) => std::thread::sleep(delay),
+                None => return Err(err),
+            },
+        }
+    }
+}
+
+fn read(path: &Path) -> std::io::Result<AchievementsDat> {
+    let bytes = std::fs::read(path)?;
+    // A file that is still being written may have grown or been truncated
+    // since, and a short read of it may well parse.
+    let len = std::fs::metadata(path)?.len();
+    if u64::try_from(bytes.len()) != Ok(len) {
+        return Err(std::io::Error::other(format!(
+            "Read {} bytes, but the file is now {len} bytes long",
+            bytes.len()
+        )));
+    }
+    AchievementsDat::parse(&mut std::io::Cursor::new(&bytes))
+        .map(|outcome| outcome.data)
+        .map_err(|err| std::io::Error::other(err.to_string()))
+}
+
+/// The current time of day in UTC, as in "12:34:56".
+fn time_of_day() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+        % 86400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}